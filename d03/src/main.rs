@@ -1,5 +1,13 @@
+use std::env;
+use std::fs::File;
+use std::io::{self, BufReader};
+
+use report::{Format, Report};
 use rs_utils::input::read_input;
 
+mod report;
+mod stream;
+
 const TEST_INPUT: &str = "987654321111111
 811111111111119
 234234234234278
@@ -34,42 +42,110 @@ fn get_next_max_digit(batteries: &[u8], start_index: usize, remaining_count: usi
     (max_digit, start_index + local_idx)
 }
 
-/// Calculate the maximum joltage by greedily selecting `num` digits.
-fn get_max_joltage(batteries: &[u8], num: usize) -> u64 {
-    let mut result: u64 = 0;
+/// Greedily select the indices of the `num` batteries giving the maximum joltage.
+fn select_batteries(batteries: &[u8], num: usize) -> Vec<usize> {
+    let mut selected = Vec::with_capacity(num);
     let mut last_index = 0;
 
     for remaining in (1..=num).rev() {
-        let (digit, found_index) = get_next_max_digit(batteries, last_index, remaining);
-        result = result * 10 + digit as u64;
+        let (_, found_index) = get_next_max_digit(batteries, last_index, remaining);
+        selected.push(found_index);
         last_index = found_index + 1;
     }
 
-    result
+    selected
+}
+
+/// Calculate the maximum joltage by greedily selecting `num` digits.
+/// The selected digits are read as a number in the given `radix`, giving
+/// `None` if that doesn't fit in a u64.
+fn get_max_joltage(batteries: &[u8], num: usize, radix: u32) -> Option<u64> {
+    select_batteries(batteries, num)
+        .iter()
+        .try_fold(0u64, |result, &i| {
+            result
+                .checked_mul(radix as u64)?
+                .checked_add(batteries[i] as u64)
+        })
+}
+
+/// Largest radix a bank can use: ratings `0-9` then `a-z`.
+const MAX_RADIX: u32 = 36;
+
+#[derive(Debug, PartialEq)]
+enum BankError {
+    /// The radix is outside `2..=36`
+    InvalidRadix(u32),
+    /// A rating that isn't a digit in the bank's radix, with its position in the line
+    InvalidRating { index: usize, rating: char },
+}
+
+impl std::fmt::Display for BankError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidRadix(radix) => {
+                write!(f, "radix {} is not in 2..={}", radix, MAX_RADIX)
+            }
+            Self::InvalidRating { index, rating } => {
+                write!(f, "invalid rating '{}' at index {}", rating, index)
+            }
+        }
+    }
 }
 
 struct Bank {
     batteries: Vec<u8>,
+    radix: u32,
 }
 
 impl Bank {
     fn new(input: &str) -> Self {
+        Self::with_radix(input, 10).expect("expected digit")
+    }
+
+    /// Parse a bank whose ratings are digits in `radix` (case insensitive).
+    fn with_radix(input: &str, radix: u32) -> Result<Self, BankError> {
+        if !(2..=MAX_RADIX).contains(&radix) {
+            return Err(BankError::InvalidRadix(radix));
+        }
+
         let batteries = input
             .chars()
-            .map(|c| c.to_digit(10).expect("expected digit") as u8)
-            .collect();
+            .enumerate()
+            .map(|(index, c)| {
+                c.to_digit(radix)
+                    .map(|d| d as u8)
+                    .ok_or(BankError::InvalidRating { index, rating: c })
+            })
+            .collect::<Result<_, _>>()?;
 
-        Bank { batteries }
+        Ok(Bank { batteries, radix })
     }
 
-    fn max_joltage(&self, num: usize) -> u64 {
-        get_max_joltage(&self.batteries, num)
+    fn len(&self) -> usize {
+        self.batteries.len()
+    }
+
+    fn max_joltage(&self, num: usize) -> Option<u64> {
+        get_max_joltage(&self.batteries, num, self.radix)
+    }
+
+    /// The ratings of the batteries chosen for the maximum `num`-battery joltage.
+    fn chosen(&self, num: usize) -> String {
+        select_batteries(&self.batteries, num)
+            .iter()
+            .map(|&i| self.rating_char(i))
+            .collect()
+    }
+
+    fn rating_char(&self, index: usize) -> char {
+        char::from_digit(self.batteries[index] as u32, self.radix).expect("rating within radix")
     }
 }
 
 impl std::fmt::Display for Bank {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let s: String = self.batteries.iter().map(|&b| (b + b'0') as char).collect();
+        let s: String = (0..self.len()).map(|i| self.rating_char(i)).collect();
         write!(f, "Bank {{ {} }}", s)
     }
 }
@@ -77,18 +153,97 @@ impl std::fmt::Display for Bank {
 fn part_1(input: &str) -> u64 {
     parse(input)
         .iter()
-        .map(|line| Bank::new(line).max_joltage(2))
+        .map(|line| {
+            Bank::new(line)
+                .max_joltage(2)
+                .expect("joltage overflows u64")
+        })
         .sum()
 }
 
 fn part_2(input: &str) -> u64 {
     parse(input)
         .iter()
-        .map(|line| Bank::new(line).max_joltage(12))
+        .map(|line| {
+            Bank::new(line)
+                .max_joltage(12)
+                .expect("joltage overflows u64")
+        })
         .sum()
 }
 
+/// Flags shared by the `stream` and `report` subcommands.
+struct Options {
+    path: String,
+    radix: u32,
+    nums: Vec<usize>,
+    format: Format,
+}
+
+impl Options {
+    fn parse(args: &[String]) -> Self {
+        let path = args
+            .first()
+            .expect("usage: d03 <stream|report> <path> [--radix N] [--num N]... [--format F]");
+        let mut options = Options {
+            path: path.clone(),
+            radix: 10,
+            nums: Vec::new(),
+            format: Format::Table,
+        };
+
+        let mut flags = args[1..].iter();
+        while let Some(flag) = flags.next() {
+            let value = flags.next().expect("flag is missing a value");
+            match flag.as_str() {
+                "--radix" => options.radix = value.parse().expect("radix must be a number"),
+                "--num" => options
+                    .nums
+                    .push(value.parse().expect("num must be a number")),
+                "--format" => options.format = value.parse().unwrap_or_else(|e| panic!("{e}")),
+                f => panic!("Unknown flag {f}"),
+            }
+        }
+
+        if options.nums.is_empty() {
+            options.nums = vec![2, 12];
+        }
+
+        options
+    }
+}
+
+/// Stream totals for a bank file of any size
+fn run_stream(options: &Options) {
+    let file = File::open(&options.path).expect("Failed to open bank file");
+    let totals = stream::stream_totals(BufReader::new(file), &options.nums, options.radix)
+        .unwrap_or_else(|e| panic!("{}: {}", options.path, e));
+
+    println!("Banks: {}", totals.banks);
+    for (num, total) in options.nums.iter().zip(totals.totals) {
+        println!("Total ({} batteries): {}", num, total);
+    }
+}
+
+/// Print the per-bank report in the requested format
+fn run_report(options: &Options) {
+    let input = read_input(&options.path);
+    let report = Report::build(&input, &options.nums, options.radix)
+        .unwrap_or_else(|e| panic!("{}: {}", options.path, e));
+
+    report
+        .write(&mut io::stdout().lock(), options.format)
+        .expect("Failed to write report");
+}
+
 fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("stream") => return run_stream(&Options::parse(&args[1..])),
+        Some("report") => return run_report(&Options::parse(&args[1..])),
+        _ => {}
+    }
+
     let input = read_input("./input.txt");
     println!("Part 1 test: {}", part_1(TEST_INPUT));
     println!("Part 1: {}", part_1(&input));
@@ -113,30 +268,60 @@ mod tests {
     #[test]
     fn test_get_max_joltage_2_cell() {
         let bank = Bank::new("987654321111111");
-        assert_eq!(bank.max_joltage(2), 98);
+        assert_eq!(bank.max_joltage(2), Some(98));
     }
 
     #[test]
     fn test_get_max_joltage_12_cell_one() {
         let bank = Bank::new("987654321111111");
-        assert_eq!(bank.max_joltage(12), 987654321111);
+        assert_eq!(bank.max_joltage(12), Some(987654321111));
     }
 
     #[test]
     fn test_get_max_joltage_12_cell_two() {
         let bank = Bank::new("811111111111119");
-        assert_eq!(bank.max_joltage(12), 811111111119);
+        assert_eq!(bank.max_joltage(12), Some(811111111119));
     }
 
     #[test]
     fn test_get_max_joltage_12_cell_three() {
         let bank = Bank::new("234234234234278");
-        assert_eq!(bank.max_joltage(12), 434234234278);
+        assert_eq!(bank.max_joltage(12), Some(434234234278));
     }
 
     #[test]
     fn test_get_max_joltage_12_cell_four() {
         let bank = Bank::new("818181911112111");
-        assert_eq!(bank.max_joltage(12), 888911112111);
+        assert_eq!(bank.max_joltage(12), Some(888911112111));
+    }
+
+    #[test]
+    fn test_hex_bank() {
+        let bank = Bank::with_radix("1f0a9", 16).unwrap();
+        assert_eq!(bank.max_joltage(2), Some(0xfa));
+        assert_eq!(bank.max_joltage(3), Some(0xfa9));
+        assert_eq!(bank.to_string(), "Bank { 1f0a9 }");
+    }
+
+    #[test]
+    fn test_base_36_bank() {
+        let bank = Bank::with_radix("z0Y", 36).unwrap();
+        assert_eq!(bank.max_joltage(2), Some(35 * 36 + 34));
+        assert_eq!(bank.to_string(), "Bank { z0y }");
+    }
+
+    #[test]
+    fn test_invalid_rating() {
+        assert_eq!(
+            Bank::with_radix("12a", 10).err(),
+            Some(BankError::InvalidRating {
+                index: 2,
+                rating: 'a'
+            })
+        );
+        assert_eq!(
+            Bank::with_radix("12", 37).err(),
+            Some(BankError::InvalidRadix(37))
+        );
     }
 }
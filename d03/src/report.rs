@@ -0,0 +1,394 @@
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+use crate::Bank;
+use crate::stream::StreamError;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Table,
+    Csv,
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "table" => Ok(Format::Table),
+            "csv" => Ok(Format::Csv),
+            "json" => Ok(Format::Json),
+            f => Err(format!("Unknown report format {f}")),
+        }
+    }
+}
+
+/// The batteries chosen for one `num` and the joltage they produce.
+#[derive(Debug, PartialEq)]
+struct Pick {
+    digits: String,
+    joltage: u64,
+}
+
+/// One bank of the input, with a pick for every requested `num`.
+#[derive(Debug, PartialEq)]
+struct BankRow {
+    /// 1-based line number in the input
+    line: usize,
+    picks: Vec<Pick>,
+}
+
+/// Aggregates over every bank for a single `num`.
+#[derive(Debug, PartialEq)]
+struct NumStats {
+    num: usize,
+    total: u128,
+    min: u64,
+    max: u64,
+    mean: f64,
+    /// How many banks' joltage starts with each rating
+    leading: BTreeMap<char, usize>,
+}
+
+impl NumStats {
+    fn from_rows(rows: &[BankRow], index: usize, num: usize) -> Self {
+        let mut stats = NumStats {
+            num,
+            total: 0,
+            min: u64::MAX,
+            max: 0,
+            mean: 0.0,
+            leading: BTreeMap::new(),
+        };
+
+        for pick in rows.iter().map(|row| &row.picks[index]) {
+            stats.total += pick.joltage as u128;
+            stats.min = stats.min.min(pick.joltage);
+            stats.max = stats.max.max(pick.joltage);
+            if let Some(lead) = pick.digits.chars().next() {
+                *stats.leading.entry(lead).or_default() += 1;
+            }
+        }
+
+        if rows.is_empty() {
+            stats.min = 0;
+        } else {
+            stats.mean = stats.total as f64 / rows.len() as f64;
+        }
+
+        stats
+    }
+
+    fn histogram(&self, separator: &str) -> String {
+        self.leading
+            .iter()
+            .rev()
+            .map(|(lead, count)| format!("{lead}:{count}"))
+            .collect::<Vec<String>>()
+            .join(separator)
+    }
+}
+
+/// Per-bank breakdown of the chosen batteries, plus aggregate statistics.
+#[derive(Debug, PartialEq)]
+pub struct Report {
+    nums: Vec<usize>,
+    rows: Vec<BankRow>,
+    stats: Vec<NumStats>,
+}
+
+impl Report {
+    /// Build a report for every non-blank line of `input`, reading ratings in `radix`.
+    pub fn build(input: &str, nums: &[usize], radix: u32) -> Result<Self, StreamError> {
+        let mut rows = Vec::new();
+
+        for (i, text) in input.lines().enumerate() {
+            let line = i + 1;
+            let text = text.trim();
+            if text.is_empty() {
+                continue;
+            }
+
+            let bank =
+                Bank::with_radix(text, radix).map_err(|error| StreamError::Bank { line, error })?;
+
+            let picks = nums
+                .iter()
+                .map(|&num| {
+                    if bank.len() < num {
+                        return Err(StreamError::TooShort {
+                            line,
+                            len: bank.len(),
+                            num,
+                        });
+                    }
+
+                    Ok(Pick {
+                        digits: bank.chosen(num),
+                        joltage: bank
+                            .max_joltage(num)
+                            .ok_or(StreamError::Overflow { line, num })?,
+                    })
+                })
+                .collect::<Result<_, _>>()?;
+
+            rows.push(BankRow { line, picks });
+        }
+
+        let stats = nums
+            .iter()
+            .enumerate()
+            .map(|(index, &num)| NumStats::from_rows(&rows, index, num))
+            .collect();
+
+        Ok(Report {
+            nums: nums.to_vec(),
+            rows,
+            stats,
+        })
+    }
+
+    pub fn write<W: Write>(&self, w: &mut W, format: Format) -> io::Result<()> {
+        match format {
+            Format::Table => self.write_table(w),
+            Format::Csv => self.write_csv(w),
+            Format::Json => self.write_json(w),
+        }
+    }
+
+    fn write_table<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        let mut header = vec!["Line".to_string()];
+        for num in &self.nums {
+            header.push(format!("Digits ({num})"));
+            header.push(format!("Joltage ({num})"));
+        }
+
+        let body: Vec<Vec<String>> = self
+            .rows
+            .iter()
+            .map(|row| {
+                let mut cells = vec![row.line.to_string()];
+                for pick in &row.picks {
+                    cells.push(pick.digits.clone());
+                    cells.push(pick.joltage.to_string());
+                }
+                cells
+            })
+            .collect();
+
+        write_aligned(w, &header, &body)?;
+        writeln!(w)?;
+
+        let header: Vec<String> = ["Num", "Total", "Min", "Max", "Mean", "Leading digits"]
+            .iter()
+            .map(|h| h.to_string())
+            .collect();
+        let body: Vec<Vec<String>> = self
+            .stats
+            .iter()
+            .map(|s| {
+                vec![
+                    s.num.to_string(),
+                    s.total.to_string(),
+                    s.min.to_string(),
+                    s.max.to_string(),
+                    format!("{:.2}", s.mean),
+                    s.histogram(" "),
+                ]
+            })
+            .collect();
+
+        write_aligned(w, &header, &body)
+    }
+
+    fn write_csv<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        write!(w, "line")?;
+        for num in &self.nums {
+            write!(w, ",digits_{num},joltage_{num}")?;
+        }
+        writeln!(w)?;
+
+        for row in &self.rows {
+            write!(w, "{}", row.line)?;
+            for pick in &row.picks {
+                write!(w, ",{},{}", pick.digits, pick.joltage)?;
+            }
+            writeln!(w)?;
+        }
+
+        // Statistics follow as a second table after a blank line
+        writeln!(w)?;
+        writeln!(w, "num,total,min,max,mean,leading_digits")?;
+        for s in &self.stats {
+            writeln!(
+                w,
+                "{},{},{},{},{},{}",
+                s.num,
+                s.total,
+                s.min,
+                s.max,
+                s.mean,
+                s.histogram(";")
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn write_json<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        // Digits are always [0-9a-z], so nothing needs escaping
+        let banks = self
+            .rows
+            .iter()
+            .map(|row| {
+                let picks = self
+                    .nums
+                    .iter()
+                    .zip(&row.picks)
+                    .map(|(num, p)| {
+                        format!(
+                            r#"{{"num":{},"digits":"{}","joltage":{}}}"#,
+                            num, p.digits, p.joltage
+                        )
+                    })
+                    .collect::<Vec<String>>()
+                    .join(",");
+                format!(r#"{{"line":{},"picks":[{}]}}"#, row.line, picks)
+            })
+            .collect::<Vec<String>>()
+            .join(",");
+
+        let stats = self
+            .stats
+            .iter()
+            .map(|s| {
+                let leading = s
+                    .leading
+                    .iter()
+                    .rev()
+                    .map(|(lead, count)| format!(r#""{lead}":{count}"#))
+                    .collect::<Vec<String>>()
+                    .join(",");
+                format!(
+                    r#"{{"num":{},"total":{},"min":{},"max":{},"mean":{},"leading_digits":{{{}}}}}"#,
+                    s.num, s.total, s.min, s.max, s.mean, leading
+                )
+            })
+            .collect::<Vec<String>>()
+            .join(",");
+
+        writeln!(w, r#"{{"banks":[{}],"stats":[{}]}}"#, banks, stats)
+    }
+}
+
+/// Write a header and rows with every column padded to its widest cell.
+fn write_aligned<W: Write>(w: &mut W, header: &[String], body: &[Vec<String>]) -> io::Result<()> {
+    let widths: Vec<usize> = (0..header.len())
+        .map(|col| {
+            body.iter()
+                .map(|row| row[col].len())
+                .chain(std::iter::once(header[col].len()))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let write_row = |w: &mut W, cells: &[String]| -> io::Result<()> {
+        let line = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, &width)| format!("{cell:>width$}"))
+            .collect::<Vec<String>>()
+            .join(" | ");
+        writeln!(w, "{}", line)
+    };
+
+    write_row(w, header)?;
+    let rule = widths
+        .iter()
+        .map(|&width| "-".repeat(width))
+        .collect::<Vec<String>>()
+        .join("-+-");
+    writeln!(w, "{}", rule)?;
+    for row in body {
+        write_row(w, row)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TEST_INPUT;
+
+    fn render(format: Format) -> String {
+        let report = Report::build(TEST_INPUT, &[2, 12], 10).unwrap();
+        let mut out = Vec::new();
+        report.write(&mut out, format).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_report_overflow() {
+        // 19 nines still fit in a u64, 20 don't
+        let nines = "9".repeat(20);
+        let report = Report::build(&nines, &[19], 10).unwrap();
+        assert_eq!(report.stats[0].total, 10u128.pow(19) - 1);
+
+        let input = format!("{}1\n{nines}", "0".repeat(19));
+        let err = Report::build(&input, &[1, 20], 10).unwrap_err();
+        assert!(matches!(err, StreamError::Overflow { line: 2, num: 20 }));
+
+        let err = Report::build(&"z".repeat(13), &[13], 36).unwrap_err();
+        assert!(matches!(err, StreamError::Overflow { line: 1, num: 13 }));
+    }
+
+    #[test]
+    fn test_report_stats() {
+        let report = Report::build(TEST_INPUT, &[2, 12], 10).unwrap();
+        let stats = &report.stats[0];
+
+        assert_eq!(stats.total, 357);
+        assert_eq!(stats.min, 78);
+        assert_eq!(stats.max, 98);
+        assert_eq!(stats.mean, 89.25);
+        assert_eq!(stats.histogram(" "), "9:2 8:1 7:1");
+        assert_eq!(report.stats[1].total, 3121910778619);
+    }
+
+    #[test]
+    fn test_report_csv() {
+        let csv = render(Format::Csv);
+        let mut lines = csv.lines();
+
+        assert_eq!(
+            lines.next(),
+            Some("line,digits_2,joltage_2,digits_12,joltage_12")
+        );
+        assert_eq!(lines.next(), Some("1,98,98,987654321111,987654321111"));
+        assert!(csv.contains("2,357,78,98,89.25,9:2;8:1;7:1"));
+    }
+
+    #[test]
+    fn test_report_json() {
+        let json = render(Format::Json);
+
+        assert!(
+            json.starts_with(
+                r#"{"banks":[{"line":1,"picks":[{"num":2,"digits":"98","joltage":98},"#
+            )
+        );
+        assert!(json.contains(r#""leading_digits":{"9":2,"8":1,"7":1}"#));
+    }
+
+    #[test]
+    fn test_report_table() {
+        let table = render(Format::Table);
+
+        assert!(table.starts_with("Line | Digits (2) | Joltage (2) |"));
+        assert!(table.contains("   4 |         92 |          92 |"));
+    }
+}
@@ -0,0 +1,300 @@
+use std::fmt;
+use std::io::{self, BufRead};
+
+use crate::{BankError, MAX_RADIX};
+
+/// Incrementally tracks the best joltage for every battery count up to a maximum,
+/// one rating at a time, so a bank never has to be held in memory.
+///
+/// `best[j]` is the largest value formed by picking `j` batteries from the ratings
+/// seen so far. Extending the best `j - 1` pick with the new rating is always at
+/// least as good as extending any other pick of the same length.
+pub struct JoltageAccumulator {
+    radix: u64,
+    best: Vec<Option<u64>>,
+    len: usize,
+    /// The fewest batteries whose best pick no longer fits in a u64. Picking
+    /// more batteries from the same bank can only give a larger joltage, so
+    /// every count from here up overflows too.
+    overflow: usize,
+}
+
+impl JoltageAccumulator {
+    pub fn new(max_num: usize, radix: u32) -> Self {
+        let mut best = vec![None; max_num + 1];
+        best[0] = Some(0);
+
+        JoltageAccumulator {
+            radix: radix as u64,
+            best,
+            len: 0,
+            overflow: usize::MAX,
+        }
+    }
+
+    pub fn push(&mut self, rating: u8) {
+        // Walk backwards so each rating is only used once per pick
+        for j in (1..self.best.len()).rev() {
+            if let Some(prev) = self.best[j - 1] {
+                match prev
+                    .checked_mul(self.radix)
+                    .and_then(|v| v.checked_add(rating as u64))
+                {
+                    Some(candidate) => {
+                        if self.best[j].is_none_or(|b| candidate > b) {
+                            self.best[j] = Some(candidate);
+                        }
+                    }
+                    None => self.overflow = self.overflow.min(j),
+                }
+            }
+        }
+        self.len += 1;
+    }
+
+    /// Number of ratings pushed since the last reset.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The maximum joltage using `num` batteries, or `None` if the bank is too
+    /// short or the joltage doesn't fit in a u64.
+    pub fn joltage(&self, num: usize) -> Option<u64> {
+        if num >= self.overflow {
+            return None;
+        }
+        self.best[num]
+    }
+
+    pub fn reset(&mut self) {
+        self.best.iter_mut().skip(1).for_each(|b| *b = None);
+        self.len = 0;
+        self.overflow = usize::MAX;
+    }
+}
+
+#[derive(Debug)]
+pub enum StreamError {
+    Io(io::Error),
+    /// The radix is outside `2..=36`
+    InvalidRadix(u32),
+    /// A bank on the given (1-based) line failed to parse
+    Bank {
+        line: usize,
+        error: BankError,
+    },
+    /// A bank on the given line has fewer than `num` batteries
+    TooShort {
+        line: usize,
+        len: usize,
+        num: usize,
+    },
+    /// The best `num` batteries of the bank on the given line make a joltage
+    /// too large for a u64
+    Overflow {
+        line: usize,
+        num: usize,
+    },
+}
+
+impl fmt::Display for StreamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{}", e),
+            Self::InvalidRadix(radix) => {
+                write!(f, "radix {} is not in 2..={}", radix, MAX_RADIX)
+            }
+            Self::Bank { line, error } => write!(f, "line {}: {}", line, error),
+            Self::TooShort { line, len, num } => {
+                write!(f, "line {}: bank has {} batteries, need {}", line, len, num)
+            }
+            Self::Overflow { line, num } => write!(
+                f,
+                "line {}: joltage of {} batteries does not fit in a u64",
+                line, num
+            ),
+        }
+    }
+}
+
+impl From<io::Error> for StreamError {
+    fn from(e: io::Error) -> Self {
+        StreamError::Io(e)
+    }
+}
+
+/// Aggregated joltage across every bank of a stream.
+#[derive(Debug, PartialEq)]
+pub struct StreamTotals {
+    pub banks: usize,
+    /// One total per requested `num`, in the order requested
+    pub totals: Vec<u128>,
+}
+
+/// Sum the maximum joltage of every bank read from `reader` for each of `nums`.
+///
+/// Ratings are consumed straight out of the reader's buffer, so memory use is
+/// independent of both the number of banks and the length of each bank.
+/// Blank lines are skipped.
+pub fn stream_totals<R: BufRead>(
+    mut reader: R,
+    nums: &[usize],
+    radix: u32,
+) -> Result<StreamTotals, StreamError> {
+    if !(2..=MAX_RADIX).contains(&radix) {
+        return Err(StreamError::InvalidRadix(radix));
+    }
+
+    let max_num = nums.iter().copied().max().unwrap_or(0);
+    let mut acc = JoltageAccumulator::new(max_num, radix);
+    let mut result = StreamTotals {
+        banks: 0,
+        totals: vec![0; nums.len()],
+    };
+    let mut line = 1;
+
+    loop {
+        let buf = reader.fill_buf()?;
+        if buf.is_empty() {
+            break;
+        }
+
+        for &byte in buf {
+            match byte {
+                b'\n' => {
+                    finish_bank(&acc, nums, line, &mut result)?;
+                    acc.reset();
+                    line += 1;
+                }
+                b'\r' => {}
+                _ => {
+                    let rating = (byte as char).to_digit(radix).ok_or(StreamError::Bank {
+                        line,
+                        error: BankError::InvalidRating {
+                            index: acc.len(),
+                            rating: byte as char,
+                        },
+                    })?;
+                    acc.push(rating as u8);
+                }
+            }
+        }
+
+        let consumed = buf.len();
+        reader.consume(consumed);
+    }
+
+    // The last bank may not have a trailing newline
+    finish_bank(&acc, nums, line, &mut result)?;
+
+    Ok(result)
+}
+
+fn finish_bank(
+    acc: &JoltageAccumulator,
+    nums: &[usize],
+    line: usize,
+    result: &mut StreamTotals,
+) -> Result<(), StreamError> {
+    if acc.is_empty() {
+        return Ok(());
+    }
+
+    for (total, &num) in result.totals.iter_mut().zip(nums) {
+        if acc.len() < num {
+            return Err(StreamError::TooShort {
+                line,
+                len: acc.len(),
+                num,
+            });
+        }
+        let joltage = acc
+            .joltage(num)
+            .ok_or(StreamError::Overflow { line, num })?;
+        *total += joltage as u128;
+    }
+    result.banks += 1;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TEST_INPUT;
+
+    #[test]
+    fn test_stream_matches_parts() {
+        let totals = stream_totals(TEST_INPUT.as_bytes(), &[2, 12], 10).unwrap();
+        assert_eq!(
+            totals,
+            StreamTotals {
+                banks: 4,
+                totals: vec![357, 3121910778619]
+            }
+        );
+    }
+
+    #[test]
+    fn test_stream_small_buffer() {
+        // A tiny buffer forces banks to straddle several reads
+        let reader = io::BufReader::with_capacity(3, "1f0a9\r\n\nff1\n".as_bytes());
+        let totals = stream_totals(reader, &[2], 16).unwrap();
+        assert_eq!(totals.banks, 2);
+        assert_eq!(totals.totals, vec![0xfa + 0xff]);
+    }
+
+    #[test]
+    fn test_stream_errors() {
+        let err = stream_totals("98\n1x\n".as_bytes(), &[2], 10).unwrap_err();
+        assert!(matches!(
+            err,
+            StreamError::Bank {
+                line: 2,
+                error: BankError::InvalidRating {
+                    index: 1,
+                    rating: 'x'
+                }
+            }
+        ));
+
+        let err = stream_totals("987\n1".as_bytes(), &[2], 10).unwrap_err();
+        assert!(matches!(
+            err,
+            StreamError::TooShort {
+                line: 2,
+                len: 1,
+                num: 2
+            }
+        ));
+
+        let err = stream_totals(
+            "98
+"
+            .as_bytes(),
+            &[2],
+            37,
+        )
+        .unwrap_err();
+        assert!(matches!(err, StreamError::InvalidRadix(37)));
+    }
+
+    #[test]
+    fn test_stream_overflow() {
+        // 19 nines still fit in a u64, 20 don't
+        let nines = "9".repeat(20);
+        let totals = stream_totals(nines.as_bytes(), &[19], 10).unwrap();
+        assert_eq!(totals.totals, vec![10u128.pow(19) - 1]);
+
+        let input = format!("{}1\n{nines}", "0".repeat(19));
+        let err = stream_totals(input.as_bytes(), &[1, 20], 10).unwrap_err();
+        assert!(matches!(err, StreamError::Overflow { line: 2, num: 20 }));
+
+        let err = stream_totals("z".repeat(13).as_bytes(), &[13], 36).unwrap_err();
+        assert!(matches!(err, StreamError::Overflow { line: 1, num: 13 }));
+    }
+}
@@ -0,0 +1,217 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::bitset::Bitset;
+use crate::peel::Peeling;
+use crate::{Grid, Point};
+
+/// Which cells count as touching when grouping the core into components.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Connectivity {
+    Four,
+    Eight,
+}
+
+impl Connectivity {
+    fn offsets(&self) -> &'static [(isize, isize)] {
+        match self {
+            Self::Four => &[(0, -1), (1, 0), (0, 1), (-1, 0)],
+            Self::Eight => &[
+                (-1, -1),
+                (0, -1),
+                (1, -1),
+                (1, 0),
+                (1, 1),
+                (0, 1),
+                (-1, 1),
+                (-1, 0),
+            ],
+        }
+    }
+}
+
+impl FromStr for Connectivity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "4" => Ok(Self::Four),
+            "8" => Ok(Self::Eight),
+            c => Err(format!("Unknown connectivity {c}, expected 4 or 8")),
+        }
+    }
+}
+
+/// When a roll was removed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Depth {
+    /// Removed in this round, counting from 1
+    Round(usize),
+    /// Part of the stable core, never removed
+    Never,
+}
+
+impl fmt::Display for Depth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Round(r) => write!(f, "{}", r),
+            Self::Never => write!(f, "∞"),
+        }
+    }
+}
+
+/// A connected group of core rolls.
+#[derive(Debug, PartialEq)]
+pub struct Component {
+    pub cells: Vec<Point>,
+    /// Top left corner of the bounding box
+    pub min: Point,
+    /// Bottom right corner of the bounding box, inclusive
+    pub max: Point,
+}
+
+impl Component {
+    pub fn size(&self) -> usize {
+        self.cells.len()
+    }
+}
+
+/// What is left once peeling has converged, and how deep each roll sat.
+pub struct Analysis {
+    /// Rolls that are never removed, in row-major order
+    pub core: Vec<Point>,
+    pub components: Vec<Component>,
+    /// Depth of every cell, `None` where there was never a roll
+    depths: Vec<Option<Depth>>,
+    width: usize,
+}
+
+impl Analysis {
+    pub fn new(grid: &Grid, peeling: &Peeling, connectivity: Connectivity) -> Self {
+        let mut depths: Vec<Option<Depth>> = (0..grid.width * grid.height)
+            .map(|i| grid.occupied.get(i).then_some(Depth::Never))
+            .collect();
+
+        for (round, removed) in peeling.rounds().enumerate() {
+            for p in removed {
+                depths[grid.index(p.x, p.y)] = Some(Depth::Round(round + 1));
+            }
+        }
+
+        let core: Vec<Point> = grid
+            .positions
+            .iter()
+            .filter(|p| depths[grid.index(p.x, p.y)] == Some(Depth::Never))
+            .cloned()
+            .collect();
+
+        let components = find_components(grid, &core, connectivity);
+
+        Analysis {
+            core,
+            components,
+            depths,
+            width: grid.width,
+        }
+    }
+
+    /// The depth of the roll at `(x, y)`, or `None` if there was no roll there.
+    pub fn depth(&self, x: usize, y: usize) -> Option<Depth> {
+        self.depths[y * self.width + x]
+    }
+}
+
+/// Flood fill the core into connected components, following the grid's edge policy.
+fn find_components(grid: &Grid, core: &[Point], connectivity: Connectivity) -> Vec<Component> {
+    let mut in_core = Bitset::new(grid.width * grid.height);
+    for p in core {
+        in_core.set(grid.index(p.x, p.y));
+    }
+
+    let mut seen = Bitset::new(grid.width * grid.height);
+    let mut components = Vec::new();
+
+    for start in core {
+        if seen.get(grid.index(start.x, start.y)) {
+            continue;
+        }
+        seen.set(grid.index(start.x, start.y));
+
+        let mut stack = vec![start.clone()];
+        let mut component = Component {
+            cells: Vec::new(),
+            min: start.clone(),
+            max: start.clone(),
+        };
+
+        while let Some(p) = stack.pop() {
+            for &(dx, dy) in connectivity.offsets() {
+                let Some(n) = grid.step(p.x, p.y, dx, dy) else {
+                    continue;
+                };
+                let i = grid.index(n.x, n.y);
+
+                if in_core.get(i) && !seen.get(i) {
+                    seen.set(i);
+                    stack.push(n);
+                }
+            }
+
+            component.min.x = component.min.x.min(p.x);
+            component.min.y = component.min.y.min(p.y);
+            component.max.x = component.max.x.max(p.x);
+            component.max.y = component.max.y.max(p.y);
+            component.cells.push(p);
+        }
+
+        components.push(component);
+    }
+
+    components
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::peel::peel;
+    use crate::{TEST_INPUT, parse};
+
+    fn analyse(input: &str, connectivity: Connectivity) -> Analysis {
+        let grid = Grid::from_raw(parse(input));
+        Analysis::new(&grid, &peel(&grid), connectivity)
+    }
+
+    #[test]
+    fn test_example_core() {
+        let analysis = analyse(TEST_INPUT, Connectivity::Eight);
+
+        // 71 rolls, 43 removable
+        assert_eq!(analysis.core.len(), 28);
+        assert_eq!(analysis.components.len(), 1);
+        assert_eq!(analysis.components[0].min, Point { x: 3, y: 3 });
+        assert_eq!(analysis.components[0].max, Point { x: 8, y: 9 });
+
+        assert_eq!(analysis.depth(2, 0), Some(Depth::Round(1)));
+        assert_eq!(analysis.depth(4, 2), Some(Depth::Round(8)));
+        assert_eq!(analysis.depth(3, 3), Some(Depth::Round(9)));
+        assert_eq!(analysis.depth(4, 4), Some(Depth::Never));
+        assert_eq!(analysis.depth(0, 0), None);
+        assert_eq!(Depth::Never.to_string(), "∞");
+    }
+
+    #[test]
+    fn test_connectivity() {
+        // Two solid blocks touching only at a corner, which props both corners up
+        let input =
+            "@@@@....\n@@@@....\n@@@@....\n@@@@....\n....@@@@\n....@@@@\n....@@@@\n....@@@@";
+
+        let four = analyse(input, Connectivity::Four);
+        let eight = analyse(input, Connectivity::Eight);
+
+        assert_eq!(four.core.len(), 26);
+        assert_eq!(four.components.len(), 2);
+        assert_eq!(four.components[0].size(), 13);
+        assert_eq!(eight.components.len(), 1);
+        assert_eq!(eight.components[0].max, Point { x: 7, y: 7 });
+    }
+}
@@ -0,0 +1,44 @@
+/// A fixed-size set of bits packed into 64-bit words.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bitset {
+    words: Vec<u64>,
+}
+
+impl Bitset {
+    pub fn new(len: usize) -> Self {
+        Bitset {
+            words: vec![0; len.div_ceil(64)],
+        }
+    }
+
+    pub fn get(&self, i: usize) -> bool {
+        self.words[i / 64] & (1 << (i % 64)) != 0
+    }
+
+    pub fn set(&mut self, i: usize) {
+        self.words[i / 64] |= 1 << (i % 64);
+    }
+
+    pub fn clear(&mut self, i: usize) {
+        self.words[i / 64] &= !(1 << (i % 64));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_across_words() {
+        let mut bits = Bitset::new(130);
+        bits.set(0);
+        bits.set(64);
+        bits.set(129);
+
+        assert!(bits.get(0) && bits.get(64) && bits.get(129));
+        assert!(!bits.get(1) && !bits.get(63) && !bits.get(128));
+
+        bits.clear(64);
+        assert!(!bits.get(64) && bits.get(0));
+    }
+}
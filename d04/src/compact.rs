@@ -0,0 +1,230 @@
+use std::io::{self, BufRead, Write};
+use std::ops::Range;
+use std::thread;
+
+use crate::rule::{Edges, Rule};
+
+/// Occupancy packed one bit per cell, for grids too big to hold as chars.
+///
+/// Rows start on a word boundary, so a 10,000 x 10,000 grid takes about 12.5MB
+/// and bands of rows can be swept in parallel without sharing any state.
+pub struct CompactGrid {
+    pub width: usize,
+    pub height: usize,
+    /// Words per row
+    stride: usize,
+    words: Vec<u64>,
+    rule: Rule,
+    offsets: Vec<(isize, isize)>,
+}
+
+impl CompactGrid {
+    /// Read a grid line by line, never holding more than one line of text.
+    pub fn read<R: BufRead>(reader: R, rule: Rule) -> io::Result<Self> {
+        let mut width = 0;
+        let mut stride = 0;
+        let mut words = Vec::new();
+        let mut height = 0;
+
+        for line in reader.lines() {
+            let line = line?;
+            let line = line.trim_end();
+            if line.is_empty() {
+                continue;
+            }
+
+            if height == 0 {
+                width = line.chars().count();
+                stride = width.div_ceil(64);
+            }
+
+            if line.chars().count() != width {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("row {} has a different width to the first", height + 1),
+                ));
+            }
+
+            let row_start = words.len();
+            words.resize(row_start + stride, 0);
+
+            for (x, c) in line.chars().enumerate() {
+                if rule.is_occupied(c) {
+                    words[row_start + x / 64] |= 1 << (x % 64);
+                }
+            }
+
+            height += 1;
+        }
+
+        Ok(CompactGrid {
+            width,
+            height,
+            stride,
+            words,
+            offsets: rule.offsets((width, height)),
+            rule,
+        })
+    }
+
+    fn is_occupied(&self, x: usize, y: usize) -> bool {
+        self.words[y * self.stride + x / 64] & (1 << (x % 64)) != 0
+    }
+
+    pub fn rolls(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    fn neighbour_count(&self, x: usize, y: usize) -> usize {
+        self.offsets
+            .iter()
+            .filter(|&&offset| {
+                match self
+                    .rule
+                    .edges
+                    .step((x, y), offset, (self.width, self.height))
+                {
+                    Some((nx, ny)) => self.is_occupied(nx, ny),
+                    None => self.rule.edges == Edges::Occupied,
+                }
+            })
+            .count()
+    }
+
+    /// Accessible rolls in a band of rows, visiting only the set bits.
+    fn count_band(&self, rows: Range<usize>) -> usize {
+        let mut count = 0;
+
+        for y in rows {
+            let row = &self.words[y * self.stride..(y + 1) * self.stride];
+
+            for (w, &word) in row.iter().enumerate() {
+                let mut bits = word;
+                while bits != 0 {
+                    let x = w * 64 + bits.trailing_zeros() as usize;
+                    bits &= bits - 1;
+
+                    if self.rule.is_accessible(self.neighbour_count(x, y)) {
+                        count += 1;
+                    }
+                }
+            }
+        }
+
+        count
+    }
+
+    /// Count the rolls a forklift can reach in a single pass, splitting the
+    /// rows into one band per thread.
+    pub fn count_accessible(&self, threads: usize) -> usize {
+        let threads = threads.clamp(1, self.height.max(1));
+        let band = self.height.div_ceil(threads);
+
+        thread::scope(|scope| {
+            let handles: Vec<_> = (0..self.height)
+                .step_by(band.max(1))
+                .map(|start| {
+                    let rows = start..(start + band).min(self.height);
+                    scope.spawn(move || self.count_band(rows))
+                })
+                .collect();
+
+            handles
+                .into_iter()
+                .map(|h| h.join().expect("band worker panicked"))
+                .sum()
+        })
+    }
+}
+
+/// Write a `width` x `height` grid where each cell holds a roll with
+/// probability `density`, from a seeded xorshift generator so runs repeat.
+pub fn write_random<W: Write>(
+    w: &mut W,
+    width: usize,
+    height: usize,
+    density: f64,
+    seed: u64,
+) -> io::Result<()> {
+    let mut state = seed.max(1);
+    let cutoff = (density.clamp(0.0, 1.0) * u32::MAX as f64) as u64;
+    let mut line = Vec::with_capacity(width + 1);
+
+    for _ in 0..height {
+        line.clear();
+        for _ in 0..width {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            line.push(if (state >> 32) < cutoff { b'@' } else { b'.' });
+        }
+        line.push(b'\n');
+        w.write_all(&line)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rule::Neighbourhood;
+    use crate::{Grid, TEST_INPUT, parse, single_pass};
+
+    fn compare(input: &str, rule: Rule) {
+        let compact = CompactGrid::read(input.as_bytes(), rule.clone()).unwrap();
+        let expected = single_pass(Grid::with_rule(parse(input), rule), false);
+
+        for threads in [1, 3, 8] {
+            assert_eq!(compact.count_accessible(threads), expected);
+        }
+    }
+
+    #[test]
+    fn test_matches_grid() {
+        let mut random = Vec::new();
+        write_random(&mut random, 150, 97, 0.6, 42).unwrap();
+        let random = String::from_utf8(random).unwrap();
+
+        for input in [TEST_INPUT, random.as_str()] {
+            compare(input, Rule::default());
+            compare(
+                input,
+                Rule {
+                    edges: Edges::Wrap,
+                    neighbourhood: Neighbourhood::Radius(2),
+                    threshold: 12,
+                    ..Rule::default()
+                },
+            );
+            compare(
+                input,
+                Rule {
+                    edges: Edges::Occupied,
+                    ..Rule::default()
+                },
+            );
+        }
+
+        // Grids smaller than the neighbourhood wrap onto themselves
+        for input in ["@", "@.", "@@\n@.", "@@@\n@.@"] {
+            compare(
+                input,
+                Rule {
+                    edges: Edges::Wrap,
+                    threshold: 2,
+                    ..Rule::default()
+                },
+            );
+        }
+    }
+
+    #[test]
+    fn test_read() {
+        let compact = CompactGrid::read(TEST_INPUT.as_bytes(), Rule::default()).unwrap();
+
+        assert_eq!((compact.width, compact.height), (10, 10));
+        assert_eq!(compact.rolls(), 71);
+        assert!(CompactGrid::read("@@@\n@@\n".as_bytes(), Rule::default()).is_err());
+    }
+}
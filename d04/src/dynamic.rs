@@ -0,0 +1,238 @@
+use crate::{Grid, Point};
+
+/// A grid that rolls can be placed on and taken from, keeping the number of
+/// accessible rolls current as it goes.
+///
+/// Every cell, empty or not, tracks how many rolls are in its neighbourhood,
+/// so an update only has to touch the cells whose neighbourhood contains it:
+/// constant work for a given rule, independent of the grid's size.
+pub struct DynamicGrid {
+    grid: Grid,
+    counts: Vec<usize>,
+    /// Where each occupied cell sits in the grid's position list, so a
+    /// removal can swap it out without a search
+    slots: Vec<Option<usize>>,
+    accessible: usize,
+    /// Symbol written into the raw grid for placed rolls
+    roll: char,
+}
+
+impl DynamicGrid {
+    pub fn new(grid: Grid) -> Self {
+        let mut counts = vec![0; grid.width * grid.height];
+        let mut accessible = 0;
+
+        for y in 0..grid.height {
+            for x in 0..grid.width {
+                let i = grid.index(x, y);
+                counts[i] = grid.neighbour_count(x, y);
+
+                if grid.occupied.get(i) && grid.rule.is_accessible(counts[i]) {
+                    accessible += 1;
+                }
+            }
+        }
+
+        let mut slots = vec![None; grid.width * grid.height];
+        for (k, p) in grid.positions.iter().enumerate() {
+            slots[grid.index(p.x, p.y)] = Some(k);
+        }
+
+        let roll = grid.rule.occupied.first().copied().unwrap_or('@');
+
+        DynamicGrid {
+            grid,
+            counts,
+            slots,
+            accessible,
+            roll,
+        }
+    }
+
+    /// How many rolls a forklift can reach right now.
+    pub fn accessible(&self) -> usize {
+        self.accessible
+    }
+
+    pub fn is_accessible(&self, x: usize, y: usize) -> bool {
+        let i = self.grid.index(x, y);
+        self.grid.occupied.get(i) && self.grid.rule.is_accessible(self.counts[i])
+    }
+
+    /// Place a roll at `(x, y)`. Returns false if there already was one.
+    pub fn insert(&mut self, x: usize, y: usize) -> bool {
+        let i = self.grid.index(x, y);
+        if self.grid.occupied.get(i) {
+            return false;
+        }
+
+        self.update_watchers(x, y, true);
+        self.grid.occupied.set(i);
+        self.grid.raw[y][x] = self.roll;
+        self.slots[i] = Some(self.grid.positions.len());
+        self.grid.positions.push(Point { x, y });
+
+        if self.is_accessible(x, y) {
+            self.accessible += 1;
+        }
+
+        true
+    }
+
+    /// Take away the roll at `(x, y)`. Returns false if there wasn't one.
+    pub fn remove(&mut self, x: usize, y: usize) -> bool {
+        let i = self.grid.index(x, y);
+        if !self.grid.occupied.get(i) {
+            return false;
+        }
+
+        if self.is_accessible(x, y) {
+            self.accessible -= 1;
+        }
+
+        self.grid.occupied.clear(i);
+        self.grid.raw[y][x] = '.';
+        if let Some(k) = self.slots[i].take() {
+            self.grid.positions.swap_remove(k);
+            if let Some(moved) = self.grid.positions.get(k) {
+                let j = self.grid.index(moved.x, moved.y);
+                self.slots[j] = Some(k);
+            }
+        }
+        self.update_watchers(x, y, false);
+
+        true
+    }
+
+    /// Adjust the count of every cell that has `(x, y)` in its neighbourhood.
+    /// Those are found by stepping the offsets backwards, which matters for
+    /// neighbourhoods that aren't symmetric.
+    fn update_watchers(&mut self, x: usize, y: usize, added: bool) {
+        for k in 0..self.grid.offsets.len() {
+            let (dx, dy) = self.grid.offsets[k];
+            let Some(w) = self.grid.step(x, y, -dx, -dy) else {
+                continue;
+            };
+
+            let was = self.is_accessible(w.x, w.y);
+            let j = self.grid.index(w.x, w.y);
+            if added {
+                self.counts[j] += 1;
+            } else {
+                self.counts[j] -= 1;
+            }
+
+            match (was, self.is_accessible(w.x, w.y)) {
+                (false, true) => self.accessible += 1,
+                (true, false) => self.accessible -= 1,
+                _ => {}
+            }
+        }
+    }
+
+    pub fn grid(&self) -> &Grid {
+        &self.grid
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rule::{Edges, Neighbourhood, Rule};
+    use crate::{TEST_INPUT, parse};
+
+    /// Count accessible rolls from scratch, for comparison.
+    fn recount(dynamic: &DynamicGrid) -> usize {
+        let grid = Grid::with_rule(dynamic.grid().raw.clone(), dynamic.grid().rule.clone());
+        let count = grid
+            .positions
+            .iter()
+            .filter(|p| grid.is_accessible(p.x, p.y))
+            .count();
+
+        // The grid handed out has to agree with one built afresh
+        let inner = dynamic.grid();
+        assert_eq!(inner.positions.len(), grid.positions.len());
+        let inner_count = inner
+            .positions
+            .iter()
+            .filter(|p| inner.is_occupied(p.x, p.y) && inner.is_accessible(p.x, p.y))
+            .count();
+        assert_eq!(inner_count, count);
+
+        count
+    }
+
+    fn churn(rule: Rule) {
+        let mut dynamic = DynamicGrid::new(Grid::with_rule(parse(TEST_INPUT), rule));
+        assert_eq!(dynamic.accessible(), recount(&dynamic));
+
+        // A deterministic walk over the grid, toggling cells as it goes
+        let mut seed = 7usize;
+        for _ in 0..200 {
+            seed = (seed * 31 + 17) % 100;
+            let (x, y) = (seed % 10, seed / 10);
+
+            if !dynamic.remove(x, y) {
+                assert!(dynamic.insert(x, y));
+            }
+            assert_eq!(dynamic.accessible(), recount(&dynamic));
+        }
+    }
+
+    #[test]
+    fn test_matches_recount() {
+        assert_eq!(
+            DynamicGrid::new(Grid::from_raw(parse(TEST_INPUT))).accessible(),
+            13
+        );
+
+        churn(Rule::default());
+        churn(Rule {
+            edges: Edges::Wrap,
+            ..Rule::default()
+        });
+        churn(Rule {
+            edges: Edges::Occupied,
+            // Lopsided on purpose: cells don't see each other symmetrically
+            neighbourhood: Neighbourhood::Custom(vec![(1, 0), (2, 1), (0, -1)]),
+            threshold: 2,
+            ..Rule::default()
+        });
+    }
+
+    #[test]
+    fn test_insert_and_remove() {
+        let mut dynamic = DynamicGrid::new(Grid::from_raw(parse("@@@\n@.@\n@@@")));
+        assert_eq!(dynamic.accessible(), 4);
+
+        // Filling the hole still leaves the corners with only three neighbours
+        assert!(dynamic.insert(1, 1));
+        assert!(!dynamic.insert(1, 1));
+        assert_eq!(dynamic.accessible(), 4);
+        assert!(!dynamic.is_accessible(1, 1));
+
+        // The edges beside a removed corner still have four neighbours
+        assert!(dynamic.remove(0, 0));
+        assert!(!dynamic.remove(0, 0));
+        assert_eq!(dynamic.accessible(), 3);
+        assert!(!dynamic.is_accessible(1, 0));
+    }
+
+    #[test]
+    fn test_small_wrapped_grid() {
+        let rule = Rule {
+            edges: Edges::Wrap,
+            threshold: 1,
+            ..Rule::default()
+        };
+        let mut dynamic = DynamicGrid::new(Grid::with_rule(parse("@."), rule));
+        assert_eq!(dynamic.accessible(), 1);
+
+        // Each roll is the other's only neighbour, however many ways it wraps
+        assert!(dynamic.insert(1, 0));
+        assert_eq!(dynamic.accessible(), 0);
+        assert!(dynamic.remove(0, 0));
+        assert_eq!(dynamic.accessible(), 1);
+    }
+}
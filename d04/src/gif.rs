@@ -0,0 +1,255 @@
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+use crate::image::{Image, Rgb};
+
+/// The largest code the LZW table may hold
+const MAX_CODE: u16 = 4095;
+
+/// Writes an animated, looping GIF from frames that share one small palette.
+pub struct GifWriter<W: Write> {
+    w: W,
+    palette: Vec<Rgb>,
+    /// Bits per palette index, at least 2 as the format requires
+    depth: u8,
+    width: u16,
+    height: u16,
+}
+
+impl<W: Write> GifWriter<W> {
+    /// Fails with `InvalidInput` if either dimension is beyond the 65535
+    /// pixels a GIF can describe.
+    pub fn new(mut w: W, width: usize, height: usize, palette: &[Rgb]) -> io::Result<Self> {
+        let (Ok(width), Ok(height)) = (u16::try_from(width), u16::try_from(height)) else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{width}x{height} frames are too large for a GIF, at most 65535 per side"),
+            ));
+        };
+        let depth = (palette.len().next_power_of_two().trailing_zeros() as u8).max(2);
+        let table_size = 1 << depth;
+
+        w.write_all(b"GIF89a")?;
+        w.write_all(&width.to_le_bytes())?;
+        w.write_all(&height.to_le_bytes())?;
+        // Global colour table present, sized 2^depth
+        w.write_all(&[0x80 | ((depth - 1) << 4) | (depth - 1), 0, 0])?;
+        for i in 0..table_size {
+            w.write_all(palette.get(i).unwrap_or(&[0, 0, 0]))?;
+        }
+        // Netscape extension: loop forever
+        w.write_all(&[0x21, 0xFF, 0x0B])?;
+        w.write_all(b"NETSCAPE2.0")?;
+        w.write_all(&[0x03, 0x01, 0x00, 0x00, 0x00])?;
+
+        Ok(GifWriter {
+            w,
+            palette: palette.to_vec(),
+            depth,
+            width,
+            height,
+        })
+    }
+
+    /// Add a frame shown for `delay` hundredths of a second. Every pixel's
+    /// colour must be in the palette.
+    pub fn write_frame(&mut self, image: &Image, delay: u16) -> io::Result<()> {
+        assert_eq!(
+            (image.width, image.height),
+            (self.width as usize, self.height as usize)
+        );
+
+        let indices: Vec<u8> = image
+            .pixels()
+            .iter()
+            .map(|p| {
+                self.palette
+                    .iter()
+                    .position(|c| c == p)
+                    .expect("colour missing from palette") as u8
+            })
+            .collect();
+
+        // Graphic control extension carrying the frame delay
+        self.w.write_all(&[0x21, 0xF9, 0x04, 0x00])?;
+        self.w.write_all(&delay.to_le_bytes())?;
+        self.w.write_all(&[0x00, 0x00])?;
+
+        // Image descriptor covering the whole screen
+        self.w.write_all(&[0x2C, 0, 0, 0, 0])?;
+        self.w.write_all(&self.width.to_le_bytes())?;
+        self.w.write_all(&self.height.to_le_bytes())?;
+        self.w.write_all(&[0x00, self.depth])?;
+
+        for block in lzw_encode(self.depth, &indices).chunks(255) {
+            self.w.write_all(&[block.len() as u8])?;
+            self.w.write_all(block)?;
+        }
+        self.w.write_all(&[0x00])
+    }
+
+    pub fn finish(mut self) -> io::Result<()> {
+        self.w.write_all(&[0x3B])?;
+        self.w.flush()
+    }
+}
+
+/// Packs variable-width codes least significant bit first.
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    acc: u32,
+    bits: u8,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, size: u8) {
+        self.acc |= (code as u32) << self.bits;
+        self.bits += size;
+        while self.bits >= 8 {
+            self.bytes.push(self.acc as u8);
+            self.acc >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.bytes.push(self.acc as u8);
+        }
+        self.bytes
+    }
+}
+
+/// GIF-flavoured LZW compression of palette indices.
+fn lzw_encode(min_code_size: u8, indices: &[u8]) -> Vec<u8> {
+    let clear = 1u16 << min_code_size;
+    let end = clear + 1;
+
+    let mut out = BitWriter::default();
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut size = min_code_size + 1;
+    let mut next = end + 1;
+
+    out.write(clear, size);
+
+    let Some((&first, rest)) = indices.split_first() else {
+        out.write(end, size);
+        return out.finish();
+    };
+    let mut prefix = first as u16;
+
+    for &index in rest {
+        if let Some(&code) = table.get(&(prefix, index)) {
+            prefix = code;
+            continue;
+        }
+
+        out.write(prefix, size);
+        table.insert((prefix, index), next);
+        next += 1;
+
+        // The decoder adds its entries one code behind us, so it widens its
+        // codes once the entry before `next` no longer fits
+        if next - 1 == 1 << size && size < 12 {
+            size += 1;
+        }
+
+        if next > MAX_CODE {
+            out.write(clear, size);
+            table.clear();
+            size = min_code_size + 1;
+            next = end + 1;
+        }
+
+        prefix = index as u16;
+    }
+
+    out.write(prefix, size);
+    if next - 1 == 1 << size && size < 12 {
+        size += 1;
+    }
+    out.write(end, size);
+
+    out.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A straightforward GIF LZW decoder to check the encoder against.
+    fn lzw_decode(min_code_size: u8, data: &[u8]) -> Vec<u8> {
+        let clear = 1u16 << min_code_size;
+        let end = clear + 1;
+        let (mut acc, mut bits, mut pos) = (0u32, 0u8, 0);
+        let mut size = min_code_size + 1;
+        let mut table: Vec<Vec<u8>> = Vec::new();
+        let mut prev: Option<Vec<u8>> = None;
+        let mut out = Vec::new();
+
+        loop {
+            while bits < size {
+                acc |= (data[pos] as u32) << bits;
+                pos += 1;
+                bits += 8;
+            }
+            let code = (acc & ((1 << size) - 1)) as u16;
+            acc >>= size;
+            bits -= size;
+
+            if code == clear {
+                table = (0..clear).map(|i| vec![i as u8]).collect();
+                table.push(vec![]);
+                table.push(vec![]);
+                size = min_code_size + 1;
+                prev = None;
+                continue;
+            }
+            if code == end {
+                return out;
+            }
+
+            let entry = match (table.get(code as usize), &prev) {
+                (Some(e), _) => e.clone(),
+                (None, Some(p)) => {
+                    let mut e = p.clone();
+                    e.push(p[0]);
+                    e
+                }
+                (None, None) => panic!("bad code"),
+            };
+
+            if let Some(p) = prev {
+                let mut added = p;
+                added.push(entry[0]);
+                table.push(added);
+                if table.len() == 1 << size && size < 12 {
+                    size += 1;
+                }
+            }
+
+            out.extend(&entry);
+            prev = Some(entry);
+        }
+    }
+
+    #[test]
+    fn test_too_large() {
+        let err = GifWriter::new(Vec::new(), 65536, 10, &[[0, 0, 0]])
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(GifWriter::new(Vec::new(), 65535, 65535, &[[0, 0, 0]]).is_ok());
+    }
+
+    #[test]
+    fn test_lzw_round_trip() {
+        // Long enough, and varied enough, to widen codes and reset the table
+        let indices: Vec<u8> = (0..20_000u32).map(|i| ((i * i / 7) % 4) as u8).collect();
+
+        for input in [&indices[..], &indices[..1], &[0, 0, 0][..]] {
+            assert_eq!(lzw_decode(2, &lzw_encode(2, input)), input);
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use std::fmt::Write;
+use std::str::FromStr;
+
+use crate::Grid;
+use crate::image::{Image, Rgb};
+
+const EMPTY_COLOUR: Rgb = [24, 24, 32];
+/// The ends of the colour ramp, from no neighbours to a full neighbourhood
+const SPARSE_COLOUR: Rgb = [40, 90, 220];
+const DENSE_COLOUR: Rgb = [230, 40, 30];
+
+/// How a heatmap is drawn in the terminal.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HeatmapMode {
+    /// Each roll's neighbour count as a digit
+    Digits,
+    /// Each roll as a block coloured by its neighbour count
+    Ansi,
+}
+
+impl FromStr for HeatmapMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "digits" => Ok(Self::Digits),
+            "ansi" => Ok(Self::Ansi),
+            m => Err(format!("Unknown heatmap mode {m}, expected digits or ansi")),
+        }
+    }
+}
+
+/// The neighbour count of every roll on a grid.
+pub struct Heatmap {
+    width: usize,
+    /// `None` for cells without a roll
+    counts: Vec<Option<usize>>,
+    /// The largest count the rule allows, used to scale colours
+    max: usize,
+}
+
+impl Heatmap {
+    pub fn new(grid: &Grid) -> Self {
+        let counts = (0..grid.height)
+            .flat_map(|y| (0..grid.width).map(move |x| (x, y)))
+            .map(|(x, y)| grid.is_occupied(x, y).then(|| grid.neighbour_count(x, y)))
+            .collect();
+
+        Heatmap {
+            width: grid.width,
+            counts,
+            max: grid.offsets.len().max(1),
+        }
+    }
+
+    fn rows(&self) -> impl Iterator<Item = &[Option<usize>]> {
+        self.counts.chunks(self.width)
+    }
+
+    /// Counts as base 36 digits so larger neighbourhoods still fit one
+    /// character per cell, with '.' where there is no roll.
+    pub fn digits(&self) -> String {
+        let mut s = String::with_capacity(self.counts.len() + self.counts.len() / self.width);
+        for row in self.rows() {
+            for count in row {
+                s.push(match count {
+                    Some(c) => char::from_digit(*c as u32, 36).unwrap_or('+'),
+                    None => '.',
+                });
+            }
+            s.push('\n');
+        }
+        s
+    }
+
+    /// Two-character blocks in 24-bit ANSI colour, one per cell.
+    pub fn ansi(&self) -> String {
+        let mut s = String::new();
+        for row in self.rows() {
+            for &count in row {
+                let [r, g, b] = self.colour(count);
+                write!(s, "\x1b[48;2;{r};{g};{b}m  ").unwrap();
+            }
+            s.push_str("\x1b[0m\n");
+        }
+        s
+    }
+
+    pub fn image(&self, scale: usize) -> Image {
+        let height = self.counts.len() / self.width;
+        Image::from_cells(self.width, height, scale, |x, y| {
+            self.colour(self.counts[y * self.width + x])
+        })
+    }
+
+    fn colour(&self, count: Option<usize>) -> Rgb {
+        let Some(count) = count else {
+            return EMPTY_COLOUR;
+        };
+
+        let t = count.min(self.max) as f64 / self.max as f64;
+        let mut colour = [0; 3];
+        for (c, (lo, hi)) in colour
+            .iter_mut()
+            .zip(SPARSE_COLOUR.iter().zip(DENSE_COLOUR))
+        {
+            *c = (*lo as f64 + (hi as f64 - *lo as f64) * t).round() as u8;
+        }
+        colour
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{TEST_INPUT, parse};
+
+    #[test]
+    fn test_digits() {
+        let heatmap = Heatmap::new(&Grid::from_raw(parse(TEST_INPUT)));
+        let digits = heatmap.digits();
+
+        assert_eq!(digits.lines().next(), Some("..33.3343."));
+        // Every roll below 4 is one the forklifts could reach in part 1
+        let accessible = digits
+            .chars()
+            .filter(|c| c.to_digit(10).is_some_and(|d| d < 4))
+            .count();
+        assert_eq!(accessible, 13);
+    }
+
+    #[test]
+    fn test_colour_ramp() {
+        let heatmap = Heatmap::new(&Grid::from_raw(parse("@@\n@.")));
+
+        assert_eq!(heatmap.colour(None), EMPTY_COLOUR);
+        assert_eq!(heatmap.colour(Some(0)), SPARSE_COLOUR);
+        assert_eq!(heatmap.colour(Some(8)), DENSE_COLOUR);
+        assert!(heatmap.ansi().starts_with("\x1b[48;2;"));
+        assert_eq!(heatmap.image(2).width, 4);
+    }
+}
@@ -0,0 +1,235 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::Grid;
+use crate::gif::GifWriter;
+use crate::image::{Image, Rgb};
+use crate::peel::Peeling;
+
+const EMPTY_COLOUR: Rgb = [32, 32, 40];
+const ROLL_COLOUR: Rgb = [230, 230, 220];
+const REMOVED_COLOUR: Rgb = [220, 40, 40];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FrameFormat {
+    Ascii,
+    Ppm,
+    Gif,
+}
+
+impl FromStr for FrameFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ascii" => Ok(Self::Ascii),
+            "ppm" => Ok(Self::Ppm),
+            "gif" => Ok(Self::Gif),
+            f => Err(format!(
+                "Unknown frame format {f}, expected ascii, ppm or gif"
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Cell {
+    Empty,
+    Roll,
+    /// Removed in the round this frame shows
+    Removed,
+}
+
+/// The warehouse before any removals, then after each round of removals.
+pub struct History {
+    width: usize,
+    height: usize,
+    /// The original grid symbols, reused for rolls and empty cells in ASCII frames
+    raw: Vec<Vec<char>>,
+    frames: Vec<Vec<Cell>>,
+    /// Rolls removed in each round; `frames[r + 1]` shows round `r`
+    pub counts: Vec<usize>,
+}
+
+impl History {
+    pub fn new(grid: &Grid, peeling: &Peeling) -> Self {
+        let mut cells: Vec<Cell> = (0..grid.width * grid.height)
+            .map(|i| {
+                if grid.occupied.get(i) {
+                    Cell::Roll
+                } else {
+                    Cell::Empty
+                }
+            })
+            .collect();
+
+        let mut frames = vec![cells.clone()];
+        let mut counts = Vec::new();
+
+        for round in peeling.rounds() {
+            // Rolls marked last round are gone for good now
+            for cell in cells.iter_mut().filter(|c| **c == Cell::Removed) {
+                *cell = Cell::Empty;
+            }
+            for p in round {
+                cells[grid.index(p.x, p.y)] = Cell::Removed;
+            }
+
+            frames.push(cells.clone());
+            counts.push(round.len());
+        }
+
+        History {
+            width: grid.width,
+            height: grid.height,
+            raw: grid.raw.clone(),
+            frames,
+            counts,
+        }
+    }
+
+    fn ascii_frame(&self, frame: usize) -> String {
+        let mut s = if frame == 0 {
+            "Initial state:\n".to_string()
+        } else {
+            let count = self.counts[frame - 1];
+            let noun = if count == 1 { "roll" } else { "rolls" };
+            format!("Remove {count} {noun} of paper:\n")
+        };
+
+        for (y, row) in self.raw.iter().enumerate() {
+            for (x, &c) in row.iter().enumerate() {
+                s.push(match self.frames[frame][y * self.width + x] {
+                    Cell::Roll => c,
+                    Cell::Removed => 'x',
+                    Cell::Empty if self.frames[0][y * self.width + x] == Cell::Roll => '.',
+                    Cell::Empty => c,
+                });
+            }
+            s.push('\n');
+        }
+
+        s
+    }
+
+    fn image_frame(&self, frame: usize, scale: usize) -> Image {
+        Image::from_cells(self.width, self.height, scale, |x, y| {
+            match self.frames[frame][y * self.width + x] {
+                Cell::Empty => EMPTY_COLOUR,
+                Cell::Roll => ROLL_COLOUR,
+                Cell::Removed => REMOVED_COLOUR,
+            }
+        })
+    }
+
+    /// Write every frame into `dir`, creating it if needed, and return the
+    /// written paths. ASCII and PPM give one numbered file per frame, GIF a
+    /// single animation. `scale` is the pixel size of a cell in images.
+    pub fn export(
+        &self,
+        dir: &Path,
+        format: FrameFormat,
+        scale: usize,
+    ) -> io::Result<Vec<PathBuf>> {
+        fs::create_dir_all(dir)?;
+
+        if format == FrameFormat::Gif {
+            let path = dir.join("history.gif");
+            let palette = [EMPTY_COLOUR, ROLL_COLOUR, REMOVED_COLOUR];
+            let file = BufWriter::new(File::create(&path)?);
+            let mut gif = GifWriter::new(file, self.width * scale, self.height * scale, &palette)?;
+
+            for frame in 0..self.frames.len() {
+                // Linger on the first and last frames
+                let delay = if frame == 0 || frame == self.frames.len() - 1 {
+                    150
+                } else {
+                    40
+                };
+                gif.write_frame(&self.image_frame(frame, scale), delay)?;
+            }
+            gif.finish()?;
+
+            return Ok(vec![path]);
+        }
+
+        let mut paths = Vec::with_capacity(self.frames.len());
+
+        for frame in 0..self.frames.len() {
+            let extension = match format {
+                FrameFormat::Ascii => "txt",
+                _ => "ppm",
+            };
+            let path = dir.join(format!("frame_{frame:03}.{extension}"));
+            let mut w = BufWriter::new(File::create(&path)?);
+
+            match format {
+                FrameFormat::Ascii => w.write_all(self.ascii_frame(frame).as_bytes())?,
+                _ => self.image_frame(frame, scale).write_ppm(&mut w)?,
+            }
+            w.flush()?;
+
+            paths.push(path);
+        }
+
+        Ok(paths)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::peel::peel;
+    use crate::{TEST_INPUT, parse};
+
+    fn history() -> History {
+        let grid = Grid::from_raw(parse(TEST_INPUT));
+        History::new(&grid, &peel(&grid))
+    }
+
+    #[test]
+    fn test_ascii_frames_match_example() {
+        let history = history();
+
+        assert_eq!(history.frames.len(), 10);
+        assert_eq!(
+            history.ascii_frame(2),
+            "Remove 12 rolls of paper:
+.......x..
+.@@.x.x.@x
+x@@@@...@@
+x.@@@@..x.
+.@.@@@@.x.
+.x@@@@@@.x
+.x.@.@.@@@
+..@@@.@@@@
+.x@@@@@@@.
+....@@@...
+"
+        );
+        assert!(
+            history
+                .ascii_frame(9)
+                .starts_with("Remove 1 roll of paper:")
+        );
+    }
+
+    #[test]
+    fn test_export_files() {
+        let dir = std::env::temp_dir().join(format!("d04_history_{}", std::process::id()));
+        let history = history();
+
+        let ascii = history.export(&dir, FrameFormat::Ascii, 1).unwrap();
+        let ppm = history.export(&dir, FrameFormat::Ppm, 2).unwrap();
+        let gif = history.export(&dir, FrameFormat::Gif, 2).unwrap();
+
+        assert_eq!(ascii.len(), 10);
+        assert!(fs::read(&ppm[0]).unwrap().starts_with(b"P6\n20 20\n255\n"));
+        let gif = fs::read(&gif[0]).unwrap();
+        assert!(gif.starts_with(b"GIF89a") && gif.ends_with(&[0x3B]));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
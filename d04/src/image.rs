@@ -0,0 +1,151 @@
+use std::io::{self, Write};
+
+pub type Rgb = [u8; 3];
+
+/// An RGB raster built from a grid, with every cell drawn as a square block.
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pixels: Vec<Rgb>,
+}
+
+impl Image {
+    /// Scale a `cols` x `rows` grid of cell colours up by `scale` pixels per cell.
+    pub fn from_cells(
+        cols: usize,
+        rows: usize,
+        scale: usize,
+        cell: impl Fn(usize, usize) -> Rgb,
+    ) -> Self {
+        let (width, height) = (cols * scale, rows * scale);
+        let mut pixels = Vec::with_capacity(width * height);
+
+        for py in 0..height {
+            for px in 0..width {
+                pixels.push(cell(px / scale, py / scale));
+            }
+        }
+
+        Image {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    pub fn pixels(&self) -> &[Rgb] {
+        &self.pixels
+    }
+
+    /// Write as a binary (P6) PPM.
+    pub fn write_ppm<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        write!(w, "P6\n{} {}\n255\n", self.width, self.height)?;
+        for pixel in &self.pixels {
+            w.write_all(pixel)?;
+        }
+        Ok(())
+    }
+
+    /// Write as an 8-bit RGB PNG. The image data is stored rather than
+    /// compressed, which keeps the encoder tiny at the cost of file size.
+    pub fn write_png<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        w.write_all(b"\x89PNG\r\n\x1a\n")?;
+
+        let mut header = Vec::with_capacity(13);
+        header.extend((self.width as u32).to_be_bytes());
+        header.extend((self.height as u32).to_be_bytes());
+        // Bit depth 8, colour type 2 (RGB), default compression, filter and interlace
+        header.extend([8, 2, 0, 0, 0]);
+        write_chunk(w, b"IHDR", &header)?;
+
+        // Every scanline starts with filter type 0 (none)
+        let mut raw = Vec::with_capacity(self.height * (1 + self.width * 3));
+        for row in self.pixels.chunks(self.width.max(1)) {
+            raw.push(0);
+            for pixel in row {
+                raw.extend(pixel);
+            }
+        }
+        write_chunk(w, b"IDAT", &zlib_stored(&raw))?;
+
+        write_chunk(w, b"IEND", &[])
+    }
+}
+
+fn write_chunk<W: Write>(w: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    w.write_all(&(data.len() as u32).to_be_bytes())?;
+    w.write_all(kind)?;
+    w.write_all(data)?;
+
+    let crc = crc32(kind.iter().chain(data));
+    w.write_all(&crc.to_be_bytes())
+}
+
+/// Wrap `data` in a zlib stream of uncompressed deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    // Deflate with a 32K window, no preset dictionary, header check bits set
+    let mut out = vec![0x78, 0x01];
+    let mut blocks = data.chunks(u16::MAX as usize).peekable();
+
+    if blocks.peek().is_none() {
+        out.extend([0x01, 0x00, 0x00, 0xFF, 0xFF]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        let len = block.len() as u16;
+
+        out.push(last as u8);
+        out.extend(len.to_le_bytes());
+        out.extend((!len).to_le_bytes());
+        out.extend(block);
+    }
+
+    out.extend(adler32(data).to_be_bytes());
+    out
+}
+
+fn crc32<'a>(bytes: impl Iterator<Item = &'a u8>) -> u32 {
+    let mut crc = !0u32;
+    for &b in bytes {
+        crc ^= b as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checksums() {
+        assert_eq!(crc32(b"IEND".iter()), 0xAE42_6082);
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+    }
+
+    #[test]
+    fn test_png_layout() {
+        let image = Image::from_cells(2, 1, 3, |x, _| [x as u8 * 255, 0, 0]);
+        let mut png = Vec::new();
+        image.write_png(&mut png).unwrap();
+
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0dIHDR"));
+        assert_eq!(&png[16..24], &[0, 0, 0, 6, 0, 0, 0, 3]);
+        assert!(png.ends_with(b"IEND\xAE\x42\x60\x82"));
+    }
+}
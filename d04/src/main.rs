@@ -1,4 +1,30 @@
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::PathBuf;
+use std::thread;
+use std::time::Instant;
+
+use analysis::{Analysis, Connectivity, Depth};
+use bitset::Bitset;
+use compact::CompactGrid;
+use dynamic::DynamicGrid;
+use heatmap::{Heatmap, HeatmapMode};
+use history::{FrameFormat, History};
+use peel::peel;
 use rs_utils::input::read_input;
+use rule::{Edges, Rule};
+
+mod analysis;
+mod bitset;
+mod compact;
+mod dynamic;
+mod gif;
+mod heatmap;
+mod history;
+mod image;
+mod peel;
+mod rule;
 
 const TEST_INPUT: &str = "..@@.@@@@.
 @@@.@.@.@@
@@ -20,6 +46,7 @@ fn parse(input: &str) -> Vec<Vec<char>> {
         .collect()
 }
 
+#[derive(Debug)]
 struct Point {
     x: usize,
     y: usize,
@@ -46,8 +73,15 @@ struct Grid {
     width: usize,
     height: usize,
 
-    /// Positions of all '@' characters in the grid. Coordinates are (x, y)
+    /// Positions of all rolls in the grid. Coordinates are (x, y)
     positions: Vec<Point>,
+
+    /// Occupancy of every cell, indexed by `y * width + x`
+    occupied: Bitset,
+
+    rule: Rule,
+    /// The rule's neighbourhood, resolved once
+    offsets: Vec<(isize, isize)>,
 }
 
 impl std::fmt::Display for Grid {
@@ -64,6 +98,10 @@ impl std::fmt::Display for Grid {
 
 impl Grid {
     fn from_raw(raw: Vec<Vec<char>>) -> Self {
+        Self::with_rule(raw, Rule::default())
+    }
+
+    fn with_rule(raw: Vec<Vec<char>>, rule: Rule) -> Self {
         let width = raw[0].len();
         let height = raw.len();
         let positions = raw
@@ -72,16 +110,24 @@ impl Grid {
             .flat_map(|(y, row)| {
                 row.iter()
                     .enumerate()
-                    .filter(|&(_, &c)| c == '@')
+                    .filter(|&(_, &c)| rule.is_occupied(c))
                     .map(move |(x, _)| Point { x, y })
             })
-            .collect();
+            .collect::<Vec<Point>>();
+
+        let mut occupied = Bitset::new(width * height);
+        for p in &positions {
+            occupied.set(p.y * width + p.x);
+        }
 
         Self {
             raw,
             width,
             height,
             positions,
+            occupied,
+            offsets: rule.offsets((width, height)),
+            rule,
         }
     }
 
@@ -89,87 +135,68 @@ impl Grid {
         self.raw[y][x] = 'x';
     }
 
-    fn neighbours(&self, x: usize, y: usize) -> Vec<Point> {
-        let mut neighbours = Vec::new();
-
-        // top left
-        if x > 0 && y > 0 {
-            let p = Point { x: x - 1, y: y - 1 };
-
-            if self.positions.contains(&p) {
-                neighbours.push(p);
-            }
-        }
-
-        // top
-        if y > 0 {
-            let p = Point { x: x, y: y - 1 };
-
-            if self.positions.contains(&p) {
-                neighbours.push(p);
-            }
-        }
-
-        // top right
-        if x < self.width - 1 && y > 0 {
-            let p = Point { x: x + 1, y: y - 1 };
-
-            if self.positions.contains(&p) {
-                neighbours.push(p);
-            }
-        }
-
-        // right
-        if x < self.width - 1 {
-            let p = Point { x: x + 1, y };
+    fn index(&self, x: usize, y: usize) -> usize {
+        y * self.width + x
+    }
 
-            if self.positions.contains(&p) {
-                neighbours.push(p);
-            }
-        }
+    fn is_occupied(&self, x: usize, y: usize) -> bool {
+        self.occupied.get(self.index(x, y))
+    }
 
-        // bottom right
-        if x < self.width - 1 && y < self.height - 1 {
-            let p = Point { x: x + 1, y: y + 1 };
+    /// The cell `(dx, dy)` away from `(x, y)`, or `None` if that is off the grid.
+    fn step(&self, x: usize, y: usize, dx: isize, dy: isize) -> Option<Point> {
+        let (x, y) = self
+            .rule
+            .edges
+            .step((x, y), (dx, dy), (self.width, self.height))?;
 
-            if self.positions.contains(&p) {
-                neighbours.push(p);
-            }
-        }
+        Some(Point { x, y })
+    }
 
-        // bottom
-        if y < self.height - 1 {
-            let p = Point { x: x, y: y + 1 };
+    /// The on-grid cells of the rule's neighbourhood, occupied or not.
+    fn adjacent(&self, x: usize, y: usize) -> impl Iterator<Item = Point> + '_ {
+        self.offsets
+            .iter()
+            .filter_map(move |&(dx, dy)| self.step(x, y, dx, dy))
+    }
 
-            if self.positions.contains(&p) {
-                neighbours.push(p);
-            }
-        }
+    /// Cells that have `(x, y)` in their neighbourhood, found by stepping the
+    /// offsets backwards. The same cells as `adjacent` unless the
+    /// neighbourhood is lopsided.
+    fn watchers(&self, x: usize, y: usize) -> impl Iterator<Item = Point> + '_ {
+        self.offsets
+            .iter()
+            .filter_map(move |&(dx, dy)| self.step(x, y, -dx, -dy))
+    }
 
-        // bottom left
-        if x > 0 && y < self.height - 1 {
-            let p = Point { x: x - 1, y: y + 1 };
+    /// Occupied cells in the rule's neighbourhood, without allocating.
+    fn neighbours(&self, x: usize, y: usize) -> impl Iterator<Item = Point> + '_ {
+        self.adjacent(x, y).filter(|p| self.is_occupied(p.x, p.y))
+    }
 
-            if self.positions.contains(&p) {
-                neighbours.push(p);
-            }
+    /// Neighbours that lie off the grid, which only count when the edges are occupied.
+    fn border_count(&self, x: usize, y: usize) -> usize {
+        if self.rule.edges != Edges::Occupied {
+            return 0;
         }
 
-        // left
-        if x > 0 {
-            let p = Point { x: x - 1, y };
+        self.offsets
+            .iter()
+            .filter(|&&(dx, dy)| self.step(x, y, dx, dy).is_none())
+            .count()
+    }
 
-            if self.positions.contains(&p) {
-                neighbours.push(p);
-            }
-        }
+    fn neighbour_count(&self, x: usize, y: usize) -> usize {
+        self.neighbours(x, y).count() + self.border_count(x, y)
+    }
 
-        neighbours
+    fn is_accessible(&self, x: usize, y: usize) -> bool {
+        self.rule.is_accessible(self.neighbour_count(x, y))
     }
 }
 
-fn part_1(input: &str, is_test: bool) -> usize {
-    let mut grid = Grid::from_raw(parse(input));
+/// Count the rolls a forklift can reach in a single pass.
+fn single_pass(mut grid: Grid, is_test: bool) -> usize {
     let mut count = 0;
 
     if is_test {
@@ -177,9 +204,7 @@ fn part_1(input: &str, is_test: bool) -> usize {
     }
 
     for position in grid.positions.clone() {
-        let neighbours = grid.neighbours(position.x, position.y);
-
-        if neighbours.len() < 4 {
+        if grid.is_accessible(position.x, position.y) {
             grid.visit(position.x, position.y);
             count += 1;
         }
@@ -192,44 +217,294 @@ fn part_1(input: &str, is_test: bool) -> usize {
     count
 }
 
-fn part_2(input: &str, is_test: bool) -> usize {
-    let mut grid = Grid::from_raw(parse(input));
-    let mut count = 0;
-    let mut removed = true;
-
+/// Count the rolls that can be removed by repeatedly taking accessible ones.
+fn repeated_removal(mut grid: Grid, is_test: bool) -> usize {
     if is_test {
         println!("Grid start:\n{}", grid);
     }
 
-    while removed {
-        let mut iter_count = 0;
+    let peeling = peel(&grid);
 
-        for position in grid.positions.clone() {
-            let neighbours = grid.neighbours(position.x, position.y);
+    for p in &peeling.order {
+        grid.visit(p.x, p.y);
+    }
+
+    if is_test {
+        println!("Grid finish:\n{}", grid);
+    }
+
+    peeling.order.len()
+}
 
-            if neighbours.len() < 4 {
-                grid.visit(position.x, position.y);
-                iter_count += 1;
+fn part_1(input: &str, is_test: bool) -> usize {
+    single_pass(Grid::from_raw(parse(input)), is_test)
+}
+
+fn part_2(input: &str, is_test: bool) -> usize {
+    repeated_removal(Grid::from_raw(parse(input)), is_test)
+}
+
+/// Flags selecting how a grid is read, which rolls are accessible and where
+/// output goes.
+struct Options {
+    path: String,
+    rule: Rule,
+    out: Option<PathBuf>,
+    frames: FrameFormat,
+    /// Pixels per cell in image output
+    scale: usize,
+    connectivity: Connectivity,
+    heatmap: HeatmapMode,
+    threads: usize,
+}
+
+impl Options {
+    /// `<path> [--threshold N] [--compare lt|le|eq|ge|gt] [--neighbourhood N] [--occupied CHARS]
+    /// [--edges wall|wrap|occupied]
+    /// [--out PATH] [--frames ascii|ppm|gif] [--scale N] [--connectivity 4|8]
+    /// [--heatmap digits|ansi] [--threads N]`
+    fn parse(args: &[String]) -> Self {
+        let mut options = Options {
+            path: args.first().expect("missing grid path").clone(),
+            rule: Rule::default(),
+            out: None,
+            frames: FrameFormat::Ascii,
+            scale: 4,
+            connectivity: Connectivity::Eight,
+            heatmap: HeatmapMode::Digits,
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+        };
+
+        let mut flags = args[1..].iter();
+        while let Some(flag) = flags.next() {
+            let value = flags.next().expect("flag is missing a value");
+            match flag.as_str() {
+                "--threshold" => {
+                    options.rule.threshold = value.parse().expect("threshold must be a number")
+                }
+                "--compare" => {
+                    options.rule.comparison = value.parse().unwrap_or_else(|e| panic!("{e}"))
+                }
+                "--neighbourhood" => {
+                    options.rule.neighbourhood = value.parse().unwrap_or_else(|e| panic!("{e}"))
+                }
+                "--occupied" => options.rule.occupied = value.chars().collect(),
+                "--edges" => options.rule.edges = value.parse().unwrap_or_else(|e| panic!("{e}")),
+                "--out" => options.out = Some(PathBuf::from(value)),
+                "--frames" => options.frames = value.parse().unwrap_or_else(|e| panic!("{e}")),
+                "--scale" => options.scale = value.parse().expect("scale must be a number"),
+                "--connectivity" => {
+                    options.connectivity = value.parse().unwrap_or_else(|e| panic!("{e}"))
+                }
+                "--heatmap" => options.heatmap = value.parse().unwrap_or_else(|e| panic!("{e}")),
+                "--threads" => options.threads = value.parse().expect("threads must be a number"),
+                f => panic!("Unknown flag {f}"),
             }
         }
 
-        grid = Grid::from_raw(grid.raw);
+        options
+    }
+
+    fn grid(&self) -> Grid {
+        Grid::with_rule(parse(&read_input(&self.path)), self.rule.clone())
+    }
+}
+
+/// Print how many rolls go in each round, and export the frames if `--out` is set.
+fn run_history(options: &Options) {
+    let grid = options.grid();
+    let history = History::new(&grid, &peel(&grid));
 
-        count += iter_count;
+    for (round, count) in history.counts.iter().enumerate() {
+        println!("Round {}: {} removed", round + 1, count);
+    }
 
-        if iter_count == 0 {
-            removed = false;
+    if let Some(dir) = &options.out {
+        let paths = history
+            .export(dir, options.frames, options.scale)
+            .expect("Failed to write frames");
+        println!("Wrote {} file(s) to {}", paths.len(), dir.display());
+    }
+}
+
+/// Print the stable core's components and a map of how deep each roll sat.
+fn run_analysis(options: &Options) {
+    let grid = options.grid();
+    let analysis = Analysis::new(&grid, &peel(&grid), options.connectivity);
+
+    println!(
+        "Core: {} rolls in {} component(s)",
+        analysis.core.len(),
+        analysis.components.len()
+    );
+    for c in &analysis.components {
+        println!(
+            "  {} rolls, ({}, {}) to ({}, {})",
+            c.size(),
+            c.min.x,
+            c.min.y,
+            c.max.x,
+            c.max.y
+        );
+    }
+
+    // One character per cell: the removal round in base 36, '#' for the core
+    println!("Depths:");
+    for y in 0..grid.height {
+        let row: String = (0..grid.width)
+            .map(|x| match analysis.depth(x, y) {
+                None => '.',
+                Some(Depth::Never) => '#',
+                Some(Depth::Round(r)) => char::from_digit(r as u32, 36).unwrap_or('+'),
+            })
+            .collect();
+        println!("{}", row);
+    }
+}
+
+/// Apply `insert X Y` / `remove X Y` lines from stdin, printing the accessible
+/// count after each.
+fn run_edit(options: &Options) {
+    let mut dynamic = DynamicGrid::new(options.grid());
+    println!("Accessible: {}", dynamic.accessible());
+
+    for line in io::stdin().lock().lines() {
+        let line = line.expect("Failed to read stdin");
+        let parts: Vec<&str> = line.split_whitespace().collect();
+
+        let [op, x, y] = parts[..] else {
+            if !parts.is_empty() {
+                eprintln!("Expected `insert X Y` or `remove X Y`, got {line}");
+            }
+            continue;
+        };
+        let (Ok(x), Ok(y)) = (x.parse::<usize>(), y.parse::<usize>()) else {
+            eprintln!("Invalid coordinates in {line}");
+            continue;
+        };
+        if x >= dynamic.grid().width || y >= dynamic.grid().height {
+            eprintln!("({x}, {y}) is off the grid");
+            continue;
+        }
+
+        let before = dynamic.accessible();
+        let changed = match op {
+            "insert" => dynamic.insert(x, y),
+            "remove" => dynamic.remove(x, y),
+            o => {
+                eprintln!("Unknown operation {o}");
+                continue;
+            }
+        };
+
+        if !changed {
+            println!("{line}: no change");
+            continue;
         }
+
+        let after = dynamic.accessible();
+        println!(
+            "{line}: accessible {after} ({:+})",
+            after as isize - before as isize
+        );
     }
+}
 
-    if is_test {
-        println!("Grid finish:\n{}", grid);
+/// Print the neighbour-count heatmap, or write it as an image when `--out`
+/// names a `.png` or `.ppm` file.
+fn run_heatmap(options: &Options) {
+    let heatmap = Heatmap::new(&options.grid());
+
+    let Some(path) = &options.out else {
+        match options.heatmap {
+            HeatmapMode::Digits => print!("{}", heatmap.digits()),
+            HeatmapMode::Ansi => print!("{}", heatmap.ansi()),
+        }
+        return;
+    };
+
+    let image = heatmap.image(options.scale);
+    let mut w = BufWriter::new(File::create(path).expect("Failed to create heatmap file"));
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("png") => image.write_png(&mut w),
+        Some("ppm") => image.write_ppm(&mut w),
+        _ => panic!("Heatmap images must be .png or .ppm"),
     }
+    .and_then(|_| w.flush())
+    .expect("Failed to write heatmap");
 
-    count
+    println!("Wrote {}", path.display());
+}
+
+/// Single-pass count over a bit-packed grid, for inputs too large for `Grid`.
+fn run_large(options: &Options) {
+    let file = File::open(&options.path).expect("Failed to open grid");
+    let grid = CompactGrid::read(BufReader::new(file), options.rule.clone())
+        .unwrap_or_else(|e| panic!("{}: {}", options.path, e));
+
+    let start = Instant::now();
+    let accessible = grid.count_accessible(options.threads);
+
+    println!(
+        "{}x{} grid, {} rolls",
+        grid.width,
+        grid.height,
+        grid.rolls()
+    );
+    println!(
+        "Accessible: {} ({} threads, {:?})",
+        accessible,
+        options.threads,
+        start.elapsed()
+    );
+}
+
+/// Write a random grid to stdout: `d04 generate <W>x<H> [--density P] [--seed N]`
+fn run_generate(args: &[String]) {
+    let size = args
+        .first()
+        .expect("usage: d04 generate <W>x<H> [--density P] [--seed N]");
+    let (width, height) = size
+        .split_once('x')
+        .and_then(|(w, h)| Some((w.parse().ok()?, h.parse().ok()?)))
+        .expect("size must look like 10000x10000");
+    let mut density = 0.6;
+    let mut seed = 1;
+
+    let mut flags = args[1..].iter();
+    while let Some(flag) = flags.next() {
+        let value = flags.next().expect("flag is missing a value");
+        match flag.as_str() {
+            "--density" => density = value.parse().expect("density must be a number"),
+            "--seed" => seed = value.parse().expect("seed must be a number"),
+            f => panic!("Unknown flag {f}"),
+        }
+    }
+
+    let mut out = BufWriter::new(io::stdout().lock());
+    compact::write_random(&mut out, width, height, density, seed)
+        .and_then(|_| out.flush())
+        .expect("Failed to write grid");
 }
 
 fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("run") => {
+            let options = Options::parse(&args[1..]);
+            println!("Accessible: {}", single_pass(options.grid(), false));
+            println!("Removable: {}", repeated_removal(options.grid(), false));
+            return;
+        }
+        Some("history") => return run_history(&Options::parse(&args[1..])),
+        Some("analyse") => return run_analysis(&Options::parse(&args[1..])),
+        Some("edit") => return run_edit(&Options::parse(&args[1..])),
+        Some("heatmap") => return run_heatmap(&Options::parse(&args[1..])),
+        Some("large") => return run_large(&Options::parse(&args[1..])),
+        Some("generate") => return run_generate(&args[1..]),
+        _ => {}
+    }
+
     let input = read_input("./input.txt");
     println!("Part 1 test: {}", part_1(TEST_INPUT, true));
     println!("Part 1: {}", part_1(&input, false));
@@ -241,6 +516,7 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::rule::Neighbourhood;
 
     #[test]
     fn test_part_1() {
@@ -251,4 +527,96 @@ mod tests {
     fn test_part_2() {
         assert_eq!(part_2(TEST_INPUT, true), 43);
     }
+
+    #[test]
+    fn test_neighbour_count_edges() {
+        let grid = Grid::from_raw(parse(TEST_INPUT));
+
+        assert_eq!(grid.neighbour_count(0, 9), 1);
+        assert_eq!(grid.neighbour_count(9, 0), 3);
+        assert_eq!(grid.neighbour_count(2, 2), 6);
+
+        let neighbours: Vec<(usize, usize)> = grid.neighbours(0, 0).map(|p| (p.x, p.y)).collect();
+        assert_eq!(neighbours, vec![(1, 1), (0, 1)]);
+    }
+
+    #[test]
+    fn test_custom_rule() {
+        let rule = Rule {
+            threshold: 3,
+            neighbourhood: Neighbourhood::VonNeumann,
+            ..Rule::default()
+        };
+        let grid = || Grid::with_rule(parse("@@@\n@@@\n@@@"), rule.clone());
+
+        // Only the four corners have fewer than three orthogonal neighbours...
+        assert_eq!(single_pass(grid(), false), 4);
+        // ...after which the edges drop to one neighbour, then the centre to none
+        assert_eq!(repeated_removal(grid(), false), 9);
+    }
+
+    #[test]
+    fn test_occupied_symbols() {
+        let rule = Rule {
+            occupied: vec!['@', '#'],
+            ..Rule::default()
+        };
+        let grid = Grid::with_rule(parse("@#@\n#.#\n@#@"), rule);
+
+        assert_eq!(grid.positions.len(), 8);
+        assert_eq!(grid.neighbour_count(1, 0), 4);
+        assert_eq!(single_pass(grid, false), 4);
+    }
+
+    #[test]
+    fn test_wrapping_edges() {
+        let rule = Rule {
+            edges: Edges::Wrap,
+            ..Rule::default()
+        };
+        let grid = Grid::with_rule(parse("@.@\n...\n@.@"), rule.clone());
+
+        // Every corner touches the other three across the seams
+        assert_eq!(grid.neighbour_count(0, 0), 3);
+        assert_eq!(grid.neighbour_count(1, 1), 4);
+
+        let grid = || Grid::with_rule(parse(TEST_INPUT), rule.clone());
+        assert_eq!(single_pass(grid(), false), 2);
+        assert_eq!(repeated_removal(grid(), false), 3);
+        // On grids too small for the neighbourhood each cell counts once, and
+        // a roll never counts itself
+        let lone = Grid::with_rule(parse("@"), rule.clone());
+        assert_eq!(lone.neighbour_count(0, 0), 0);
+        assert_eq!(single_pass(lone, false), 1);
+
+        let row = Rule {
+            threshold: 1,
+            ..rule.clone()
+        };
+        assert_eq!(
+            single_pass(Grid::with_rule(parse("@."), row.clone()), false),
+            1
+        );
+        assert_eq!(single_pass(Grid::with_rule(parse("@@@@"), row), false), 0);
+
+        let square = Grid::with_rule(parse("@@\n@@"), rule.clone());
+        assert_eq!(square.neighbour_count(0, 0), 3);
+        assert_eq!(repeated_removal(square, false), 4);
+    }
+
+    #[test]
+    fn test_occupied_edges() {
+        let rule = Rule {
+            edges: Edges::Occupied,
+            ..Rule::default()
+        };
+        let grid = || Grid::with_rule(parse("@@@\n@.@\n@@@"), rule.clone());
+
+        // Corners see five border cells, edges three; the ring can't be peeled
+        assert_eq!(grid().neighbour_count(0, 0), 7);
+        assert_eq!(grid().neighbour_count(1, 0), 7);
+        assert_eq!(single_pass(grid(), false), 0);
+        assert_eq!(repeated_removal(grid(), false), 0);
+        assert_eq!(part_2("@@@\n@.@\n@@@", false), 8);
+    }
 }
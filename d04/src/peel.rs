@@ -0,0 +1,177 @@
+use crate::bitset::Bitset;
+use crate::{Grid, Point};
+
+/// The outcome of repeatedly removing accessible rolls until none are left.
+pub struct Peeling {
+    /// Every removed roll, in the order it was removed
+    pub order: Vec<Point>,
+    /// Index into `order` just past the last roll of each round
+    round_ends: Vec<usize>,
+}
+
+impl Peeling {
+    /// The rolls removed in each round, in round order.
+    pub fn rounds(&self) -> impl Iterator<Item = &[Point]> {
+        let starts = std::iter::once(0).chain(self.round_ends.iter().copied());
+        starts
+            .zip(&self.round_ends)
+            .map(|(start, &end)| &self.order[start..end])
+    }
+}
+
+/// Remove accessible rolls until the grid is stable (k-core peeling).
+///
+/// Each roll's neighbour count is computed once and then decremented as its
+/// neighbours are removed, so only the rolls watching a removed roll are ever
+/// re-examined. Rolls are removed in rounds: a round takes every roll that was
+/// accessible at the end of the previous one, matching a full sweep of the grid.
+pub fn peel(grid: &Grid) -> Peeling {
+    let mut counts = vec![0; grid.width * grid.height];
+    let mut present = grid.occupied.clone();
+    let mut touched = Bitset::new(counts.len());
+    let mut frontier = Vec::new();
+
+    for p in &grid.positions {
+        let i = grid.index(p.x, p.y);
+        counts[i] = grid.neighbour_count(p.x, p.y);
+
+        if grid.rule.is_accessible(counts[i]) {
+            frontier.push(p.clone());
+        }
+    }
+
+    let mut order = Vec::with_capacity(grid.positions.len());
+    let mut round_ends = Vec::new();
+
+    while !frontier.is_empty() {
+        // Take the whole round out first so rolls removed together don't
+        // lower each other's counts
+        for p in &frontier {
+            present.clear(grid.index(p.x, p.y));
+        }
+
+        let mut changed = Vec::new();
+
+        for p in &frontier {
+            // The rolls whose count drops are those with `p` in their
+            // neighbourhood, not those in its own
+            for n in grid.watchers(p.x, p.y) {
+                let i = grid.index(n.x, n.y);
+                if !present.get(i) {
+                    continue;
+                }
+
+                counts[i] -= 1;
+                if !touched.get(i) {
+                    touched.set(i);
+                    changed.push(n);
+                }
+            }
+        }
+
+        // Only rolls whose count changed can have become accessible. They are
+        // judged on their final count for the round, since a rule that isn't
+        // "fewer than" may flip more than once as neighbours go.
+        let next = changed
+            .into_iter()
+            .filter(|n| {
+                let i = grid.index(n.x, n.y);
+                touched.clear(i);
+                grid.rule.is_accessible(counts[i])
+            })
+            .collect();
+
+        order.append(&mut frontier);
+        round_ends.push(order.len());
+        frontier = next;
+    }
+
+    Peeling { order, round_ends }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rule::{Comparison, Neighbourhood, Rule};
+    use crate::{TEST_INPUT, parse};
+
+    /// Rolls removed per round by sweeping the whole grid each time.
+    fn sweep(input: &str, rule: &Rule) -> Vec<usize> {
+        let mut raw = parse(input);
+        let mut rounds = Vec::new();
+
+        loop {
+            let grid = Grid::with_rule(raw.clone(), rule.clone());
+            let accessible: Vec<&Point> = grid
+                .positions
+                .iter()
+                .filter(|p| grid.is_accessible(p.x, p.y))
+                .collect();
+            if accessible.is_empty() {
+                return rounds;
+            }
+
+            rounds.push(accessible.len());
+            for p in accessible {
+                raw[p.y][p.x] = '.';
+            }
+        }
+    }
+
+    #[test]
+    fn test_first_round_matches_single_pass() {
+        let grid = Grid::from_raw(parse(TEST_INPUT));
+        let peeling = peel(&grid);
+
+        assert_eq!(peeling.order.len(), 43);
+        // The first 13 removals are exactly the rolls accessible in part 1
+        assert!(
+            peeling.order[..13]
+                .iter()
+                .all(|p| grid.is_accessible(p.x, p.y))
+        );
+        assert!(!grid.is_accessible(peeling.order[13].x, peeling.order[13].y));
+    }
+
+    #[test]
+    fn test_round_counts() {
+        let peeling = peel(&Grid::from_raw(parse(TEST_INPUT)));
+        let counts: Vec<usize> = peeling.rounds().map(|r| r.len()).collect();
+
+        // The per-round removals from the puzzle's worked example
+        assert_eq!(counts, vec![13, 12, 7, 5, 2, 1, 1, 1, 1]);
+    }
+
+    #[test]
+    fn test_lopsided_matches_sweep() {
+        // Rolls that don't see each other both ways, so a removal lowers the
+        // counts of the rolls watching it rather than those it watches
+        let right = Rule {
+            neighbourhood: Neighbourhood::Custom(vec![(1, 0)]),
+            threshold: 1,
+            ..Rule::default()
+        };
+        let slanted = Rule {
+            neighbourhood: Neighbourhood::Custom(vec![(1, 0), (-1, 1), (2, 2)]),
+            threshold: 2,
+            ..Rule::default()
+        };
+        let crowded = Rule {
+            comparison: Comparison::GreaterOrEqual,
+            ..right.clone()
+        };
+
+        for input in ["@@", "@@@", TEST_INPUT] {
+            for rule in [&right, &slanted, &crowded] {
+                let rounds: Vec<usize> = peel(&Grid::with_rule(parse(input), rule.clone()))
+                    .rounds()
+                    .map(|r| r.len())
+                    .collect();
+                assert_eq!(rounds, sweep(input, rule), "{input:?} with {rule:?}");
+            }
+        }
+
+        // Only the rightmost roll is clear at first, then the next along
+        assert_eq!(sweep("@@@", &right), vec![1, 1, 1]);
+    }
+}
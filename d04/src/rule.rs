@@ -0,0 +1,305 @@
+use std::str::FromStr;
+
+/// How a roll's neighbour count is compared against the rule's threshold.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Comparison {
+    Less,
+    LessOrEqual,
+    Equal,
+    GreaterOrEqual,
+    Greater,
+}
+
+impl Comparison {
+    fn holds(&self, count: usize, threshold: usize) -> bool {
+        match self {
+            Self::Less => count < threshold,
+            Self::LessOrEqual => count <= threshold,
+            Self::Equal => count == threshold,
+            Self::GreaterOrEqual => count >= threshold,
+            Self::Greater => count > threshold,
+        }
+    }
+}
+
+impl FromStr for Comparison {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "lt" => Ok(Self::Less),
+            "le" => Ok(Self::LessOrEqual),
+            "eq" => Ok(Self::Equal),
+            "ge" => Ok(Self::GreaterOrEqual),
+            "gt" => Ok(Self::Greater),
+            c => Err(format!(
+                "Unknown comparison {c}, expected lt, le, eq, ge or gt"
+            )),
+        }
+    }
+}
+
+/// Which surrounding cells count as a roll's neighbours.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Neighbourhood {
+    /// The four orthogonal cells
+    VonNeumann,
+    /// The eight surrounding cells, clockwise from top left
+    Moore,
+    /// Every cell within the given Chebyshev distance
+    Radius(usize),
+    /// An explicit list of (dx, dy) offsets
+    Custom(Vec<(isize, isize)>),
+}
+
+impl Neighbourhood {
+    /// (dx, dy) offsets of every neighbour, never including the cell itself.
+    pub fn offsets(&self) -> Vec<(isize, isize)> {
+        match self {
+            Self::VonNeumann => vec![(0, -1), (1, 0), (0, 1), (-1, 0)],
+            Self::Moore => vec![
+                (-1, -1),
+                (0, -1),
+                (1, -1),
+                (1, 0),
+                (1, 1),
+                (0, 1),
+                (-1, 1),
+                (-1, 0),
+            ],
+            Self::Radius(r) => {
+                let r = *r as isize;
+                (-r..=r)
+                    .flat_map(|dy| (-r..=r).map(move |dx| (dx, dy)))
+                    .filter(|&offset| offset != (0, 0))
+                    .collect()
+            }
+            Self::Custom(offsets) => {
+                let mut offsets: Vec<(isize, isize)> =
+                    offsets.iter().copied().filter(|&o| o != (0, 0)).collect();
+                offsets.sort_unstable_by_key(|&(dx, dy)| (dy, dx));
+                offsets.dedup();
+                offsets
+            }
+        }
+    }
+
+    /// Parse a mask such as `.#./#.#/.#.`: rows separated by `/`, odd width and
+    /// height, centred on the roll itself, with `#` marking a neighbour.
+    fn from_mask(mask: &str) -> Result<Self, String> {
+        let rows: Vec<&str> = mask.split('/').collect();
+        let height = rows.len();
+        let width = rows[0].chars().count();
+
+        if height.is_multiple_of(2)
+            || width.is_multiple_of(2)
+            || rows.iter().any(|r| r.chars().count() != width)
+        {
+            return Err(format!("Mask {mask} must be a rectangle with odd sides"));
+        }
+
+        let (cx, cy) = ((width / 2) as isize, (height / 2) as isize);
+        let mut offsets = Vec::new();
+
+        for (y, row) in rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                match c {
+                    '#' => offsets.push((x as isize - cx, y as isize - cy)),
+                    '.' => {}
+                    c => return Err(format!("Unexpected mask character {c}")),
+                }
+            }
+        }
+
+        Ok(Self::Custom(offsets))
+    }
+}
+
+impl FromStr for Neighbourhood {
+    type Err = String;
+
+    /// `von-neumann`, `moore`, `radius:R`, `offsets:dx,dy;dx,dy...` or `mask:ROWS`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, arg) = s.split_once(':').unwrap_or((s, ""));
+
+        match kind {
+            "von-neumann" => Ok(Self::VonNeumann),
+            "moore" => Ok(Self::Moore),
+            "radius" => arg
+                .parse()
+                .map(Self::Radius)
+                .map_err(|_| format!("Invalid radius {arg}")),
+            "offsets" => arg
+                .split(';')
+                .map(|pair| {
+                    let (dx, dy) = pair
+                        .split_once(',')
+                        .ok_or(format!("Offset {pair} should be dx,dy"))?;
+                    let dx = dx.trim().parse().map_err(|_| format!("Invalid dx {dx}"))?;
+                    let dy = dy.trim().parse().map_err(|_| format!("Invalid dy {dy}"))?;
+                    Ok((dx, dy))
+                })
+                .collect::<Result<_, String>>()
+                .map(Self::Custom),
+            "mask" => Self::from_mask(arg),
+            n => Err(format!("Unknown neighbourhood {n}")),
+        }
+    }
+}
+
+/// What a neighbourhood sees when it reaches past the edge of the grid.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Edges {
+    /// Nothing lies beyond the edge
+    Wall,
+    /// The grid wraps around into a torus
+    Wrap,
+    /// Every cell beyond the edge counts as a roll that can never be removed
+    Occupied,
+}
+
+impl Edges {
+    /// The cell `(dx, dy)` away from `(x, y)` on a `width` x `height` grid, or
+    /// `None` if that is off the grid.
+    pub fn step(
+        &self,
+        (x, y): (usize, usize),
+        (dx, dy): (isize, isize),
+        (width, height): (usize, usize),
+    ) -> Option<(usize, usize)> {
+        if *self == Self::Wrap {
+            let nx = (x as isize + dx).rem_euclid(width as isize) as usize;
+            let ny = (y as isize + dy).rem_euclid(height as isize) as usize;
+            return Some((nx, ny));
+        }
+
+        let nx = x.checked_add_signed(dx).filter(|&nx| nx < width)?;
+        let ny = y.checked_add_signed(dy).filter(|&ny| ny < height)?;
+
+        Some((nx, ny))
+    }
+}
+
+impl FromStr for Edges {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "wall" => Ok(Self::Wall),
+            "wrap" => Ok(Self::Wrap),
+            "occupied" => Ok(Self::Occupied),
+            e => Err(format!(
+                "Unknown edge policy {e}, expected wall, wrap or occupied"
+            )),
+        }
+    }
+}
+
+/// Decides which cells hold rolls and which of those a forklift can reach.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rule {
+    pub threshold: usize,
+    pub comparison: Comparison,
+    pub neighbourhood: Neighbourhood,
+    pub edges: Edges,
+    /// Cell symbols that count as a roll
+    pub occupied: Vec<char>,
+}
+
+impl Default for Rule {
+    /// A roll is accessible with fewer than four rolls in the eight adjacent positions.
+    fn default() -> Self {
+        Rule {
+            threshold: 4,
+            comparison: Comparison::Less,
+            neighbourhood: Neighbourhood::Moore,
+            edges: Edges::Wall,
+            occupied: vec!['@'],
+        }
+    }
+}
+
+impl Rule {
+    pub fn is_accessible(&self, neighbour_count: usize) -> bool {
+        self.comparison.holds(neighbour_count, self.threshold)
+    }
+
+    pub fn is_occupied(&self, c: char) -> bool {
+        self.occupied.contains(&c)
+    }
+
+    /// The neighbourhood's offsets as seen from a `width` x `height` grid.
+    ///
+    /// When wrapping, a grid no wider or taller than the neighbourhood brings
+    /// some offsets back onto the roll itself, or onto a cell another offset
+    /// already reaches. Those are dropped, so each neighbour counts once.
+    pub fn offsets(&self, (width, height): (usize, usize)) -> Vec<(isize, isize)> {
+        let offsets = self.neighbourhood.offsets();
+        if self.edges != Edges::Wrap || width == 0 || height == 0 {
+            return offsets;
+        }
+
+        let mut wrapped = Vec::new();
+        for (dx, dy) in offsets {
+            let offset = (
+                dx.rem_euclid(width as isize),
+                dy.rem_euclid(height as isize),
+            );
+            if offset != (0, 0) && !wrapped.contains(&offset) {
+                wrapped.push(offset);
+            }
+        }
+        wrapped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_neighbourhood_offsets() {
+        assert_eq!(Neighbourhood::Moore.offsets().len(), 8);
+        assert_eq!(Neighbourhood::Radius(2).offsets().len(), 24);
+        assert_eq!(
+            "von-neumann".parse::<Neighbourhood>().unwrap().offsets(),
+            vec![(0, -1), (1, 0), (0, 1), (-1, 0)]
+        );
+    }
+
+    #[test]
+    fn test_parse_custom_neighbourhood() {
+        let mask: Neighbourhood = "mask:#.#/.#./#.#".parse().unwrap();
+        let offsets: Neighbourhood = "offsets:1,1;-1,-1;1,-1;-1,1;0,0".parse().unwrap();
+
+        assert_eq!(mask.offsets(), vec![(-1, -1), (1, -1), (-1, 1), (1, 1)]);
+        assert_eq!(mask.offsets(), offsets.offsets());
+        assert!("mask:##/##".parse::<Neighbourhood>().is_err());
+    }
+
+    #[test]
+    fn test_wrapped_offsets() {
+        let rule = Rule {
+            edges: Edges::Wrap,
+            ..Rule::default()
+        };
+
+        assert_eq!(rule.offsets((3, 3)).len(), 8);
+        assert_eq!(rule.offsets((1, 1)), vec![]);
+        assert_eq!(rule.offsets((2, 1)), vec![(1, 0)]);
+        assert_eq!(rule.offsets((2, 2)), vec![(1, 1), (0, 1), (1, 0)]);
+        assert_eq!(Rule::default().offsets((1, 1)).len(), 8);
+    }
+
+    #[test]
+    fn test_rule_comparison() {
+        let rule = Rule {
+            comparison: "ge".parse().unwrap(),
+            ..Rule::default()
+        };
+
+        assert!(rule.is_accessible(4));
+        assert!(!rule.is_accessible(3));
+        assert!(Rule::default().is_accessible(3));
+    }
+}
@@ -0,0 +1,94 @@
+use std::time::{Duration, Instant};
+
+use crate::FreshRange;
+use crate::set::{FreshSet, Lookup};
+
+/// IDs checked by the linear scan; it is far too slow to run over them all
+const LINEAR_SAMPLE: usize = 1_000;
+
+/// A seeded xorshift generator so every run benchmarks the same data.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+}
+
+fn time<T>(f: impl FnOnce() -> T) -> (T, Duration) {
+    let start = Instant::now();
+    let result = f();
+    (result, start.elapsed())
+}
+
+/// Compare part 1 lookups on generated data:
+/// `d05 bench [--ranges N] [--ids N] [--seed N]`
+pub fn run(args: &[String]) {
+    let mut range_count = 1_000_000;
+    let mut id_count = 1_000_000;
+    let mut seed = 1;
+
+    let mut flags = args.iter();
+    while let Some(flag) = flags.next() {
+        let value = flags.next().expect("flag is missing a value");
+        match flag.as_str() {
+            "--ranges" => range_count = value.parse().expect("ranges must be a number"),
+            "--ids" => id_count = value.parse().expect("ids must be a number"),
+            "--seed" => seed = value.parse().expect("seed must be a number"),
+            f => panic!("Unknown flag {f}"),
+        }
+    }
+
+    // Ranges spread over a space large enough that most don't overlap
+    let space = range_count as u64 * 1_000_000;
+    let mut rng = Rng(seed.max(1));
+    let ranges: Vec<FreshRange> = (0..range_count)
+        .map(|_| {
+            let min = rng.below(space);
+            FreshRange {
+                min,
+                max: min + rng.below(1_000_000),
+            }
+        })
+        .collect();
+    let ids: Vec<u64> = (0..id_count).map(|_| rng.below(space)).collect();
+
+    println!("{} ranges, {} IDs", range_count, id_count);
+
+    let sample = &ids[..id_count.min(LINEAR_SAMPLE)];
+    let (linear, linear_time) = time(|| {
+        sample
+            .iter()
+            .filter(|&&id| ranges.iter().any(|r| r.contains(id)))
+            .count()
+    });
+    let per_id = linear_time / sample.len().max(1) as u32;
+    println!(
+        "linear scan:   {:>10.2?} for {} IDs (~{:.2?} for all)",
+        linear_time,
+        sample.len(),
+        per_id * id_count as u32
+    );
+
+    let (set, build_time) = time(|| FreshSet::new(ranges.clone()));
+    println!("merge ranges:  {:>10.2?}", build_time);
+
+    let (binary, binary_time) = time(|| set.count_fresh(ids.clone(), Lookup::BinarySearch));
+    println!("binary search: {:>10.2?} ({} fresh)", binary_time, binary);
+
+    let (sweep, sweep_time) = time(|| set.count_fresh(ids.clone(), Lookup::Sweep));
+    println!("sort + sweep:  {:>10.2?} ({} fresh)", sweep_time, sweep);
+
+    assert_eq!(binary, sweep);
+    assert_eq!(
+        linear,
+        set.count_fresh(sample.to_vec(), Lookup::BinarySearch)
+    );
+}
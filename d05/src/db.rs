@@ -0,0 +1,312 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+use std::num::ParseIntError;
+use std::str::FromStr;
+
+use crate::{FreshRange, RangeError};
+
+/// Fresh ranges that can be added and removed as inventory changes.
+///
+/// Ranges are kept disjoint and non-adjacent in a map from start to end, so
+/// a point lookup is a single ordered search and the total is kept as it goes.
+#[derive(Default)]
+pub struct FreshDb {
+    ranges: BTreeMap<u64, u64>,
+    count: u128,
+}
+
+impl FreshDb {
+    pub fn new(fresh_ranges: Vec<FreshRange>) -> Self {
+        let mut db = FreshDb::default();
+        for range in fresh_ranges {
+            db.insert(range);
+        }
+        db
+    }
+
+    /// The stored range containing `id`, if any.
+    pub fn get(&self, id: u64) -> Option<FreshRange> {
+        self.ranges
+            .range(..=id)
+            .next_back()
+            .filter(|&(_, &max)| max >= id)
+            .map(|(&min, &max)| FreshRange { min, max })
+    }
+
+    /// How many IDs are fresh.
+    pub fn count(&self) -> u128 {
+        self.count
+    }
+
+    pub fn ranges(&self) -> impl Iterator<Item = FreshRange> + '_ {
+        self.ranges
+            .iter()
+            .map(|(&min, &max)| FreshRange { min, max })
+    }
+
+    fn take(&mut self, min: u64) -> FreshRange {
+        let max = self.ranges.remove(&min).expect("range to take is stored");
+        let range = FreshRange { min, max };
+        self.count -= range.len();
+        range
+    }
+
+    fn put(&mut self, range: FreshRange) {
+        self.count += range.len();
+        self.ranges.insert(range.min, range.max);
+    }
+
+    /// Mark every ID in `range` fresh, folding in any range it overlaps or touches.
+    pub fn insert(&mut self, range: FreshRange) {
+        let mut merged = range;
+
+        // A range starting below may reach into, or end right before, this one
+        if let Some(below) = merged.min.checked_sub(1).and_then(|id| self.get(id)) {
+            merged.min = below.min;
+            merged.max = merged.max.max(self.take(below.min).max);
+        }
+
+        // Then any starting inside it or right after it
+        loop {
+            let upper = merged.max.saturating_add(1);
+            let Some((&min, _)) = self.ranges.range(merged.min..=upper).next() else {
+                break;
+            };
+            merged.max = merged.max.max(self.take(min).max);
+        }
+
+        self.put(merged);
+    }
+
+    /// Mark every ID in `range` spoiled, splitting ranges that stick out either side.
+    pub fn delete(&mut self, range: FreshRange) {
+        if let Some(below) = self.get(range.min).filter(|r| r.min < range.min) {
+            self.take(below.min);
+            self.put(FreshRange {
+                min: below.min,
+                max: range.min - 1,
+            });
+            if below.max > range.max {
+                self.put(FreshRange {
+                    min: range.max + 1,
+                    max: below.max,
+                });
+                return;
+            }
+        }
+
+        while let Some((&min, _)) = self.ranges.range(range.min..=range.max).next() {
+            let inside = self.take(min);
+            if inside.max > range.max {
+                self.put(FreshRange {
+                    min: range.max + 1,
+                    max: inside.max,
+                });
+            }
+        }
+    }
+}
+
+/// One line of a database script.
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Add(FreshRange),
+    Delete(FreshRange),
+    Query(u64),
+    Count,
+    /// Print every stored range
+    List,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CommandError {
+    Unknown(String),
+    MissingArgument,
+    Range(RangeError),
+    Id(ParseIntError),
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unknown(c) => write!(
+                f,
+                "unknown command {}, expected add, del, query, count or list",
+                c
+            ),
+            Self::MissingArgument => write!(f, "command is missing its argument"),
+            Self::Range(e) => write!(f, "bad range: {}", e),
+            Self::Id(e) => write!(f, "bad ID: {}", e),
+        }
+    }
+}
+
+impl FromStr for Command {
+    type Err = CommandError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, argument) = s.trim().split_once(' ').unwrap_or((s.trim(), ""));
+        let argument = argument.trim();
+
+        if argument.is_empty() && name != "count" && name != "list" {
+            return match name {
+                "add" | "del" | "query" => Err(CommandError::MissingArgument),
+                c => Err(CommandError::Unknown(c.to_string())),
+            };
+        }
+
+        match name {
+            "add" => Ok(Self::Add(argument.parse().map_err(CommandError::Range)?)),
+            "del" => Ok(Self::Delete(argument.parse().map_err(CommandError::Range)?)),
+            "query" => Ok(Self::Query(argument.parse().map_err(CommandError::Id)?)),
+            "count" => Ok(Self::Count),
+            "list" => Ok(Self::List),
+            c => Err(CommandError::Unknown(c.to_string())),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(io::Error),
+    Command { line: usize, error: CommandError },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{}", e),
+            Self::Command { line, error } => write!(f, "line {}: {}", line, error),
+        }
+    }
+}
+
+impl From<io::Error> for ReplayError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl FreshDb {
+    /// Run `command`, writing the answer to queries and counts.
+    pub fn apply<W: Write>(&mut self, command: &Command, out: &mut W) -> io::Result<()> {
+        match *command {
+            Command::Add(range) => self.insert(range),
+            Command::Delete(range) => self.delete(range),
+            Command::Query(id) => match self.get(id) {
+                Some(r) => writeln!(out, "{} fresh ({}-{})", id, r.min, r.max)?,
+                None => writeln!(out, "{} spoiled", id)?,
+            },
+            Command::Count => writeln!(out, "{}", self.count())?,
+            Command::List => {
+                for r in self.ranges() {
+                    writeln!(out, "{}-{}", r.min, r.max)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Run a script of commands, one per line. Blank lines and `#` comments
+    /// are skipped.
+    pub fn replay<R: BufRead, W: Write>(
+        &mut self,
+        script: R,
+        out: &mut W,
+    ) -> Result<(), ReplayError> {
+        for (i, line) in script.lines().enumerate() {
+            let line = line?;
+            let text = line.split('#').next().unwrap_or_default().trim();
+            if text.is_empty() {
+                continue;
+            }
+
+            let command = text
+                .parse()
+                .map_err(|error| ReplayError::Command { line: i + 1, error })?;
+            self.apply(&command, out)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Merge, TEST_INPUT, merge_ranges, parse};
+
+    fn range(min: u64, max: u64) -> FreshRange {
+        FreshRange { min, max }
+    }
+
+    /// The stored ranges must always match merging from scratch.
+    fn check(db: &FreshDb, expected: &[FreshRange]) {
+        let ranges: Vec<FreshRange> = db.ranges().collect();
+        assert_eq!(ranges, expected);
+        assert_eq!(db.count(), ranges.iter().map(FreshRange::len).sum::<u128>());
+    }
+
+    #[test]
+    fn test_insert() {
+        let (ranges, _) = parse(TEST_INPUT).unwrap();
+        let mut db = FreshDb::new(ranges.clone());
+        check(&db, &merge_ranges(ranges, Merge::Adjacent));
+        assert_eq!(db.count(), 14);
+
+        // Touching both neighbours joins everything up
+        db.insert(range(6, 9));
+        check(&db, &[range(3, 20)]);
+
+        db.insert(range(30, u64::MAX));
+        db.insert(range(0, 1));
+        check(&db, &[range(0, 1), range(3, 20), range(30, u64::MAX)]);
+    }
+
+    #[test]
+    fn test_delete() {
+        let mut db = FreshDb::new(vec![range(3, 5), range(10, 20), range(25, 30)]);
+
+        db.delete(range(4, 4));
+        check(
+            &db,
+            &[range(3, 3), range(5, 5), range(10, 20), range(25, 30)],
+        );
+
+        db.delete(range(15, 27));
+        check(
+            &db,
+            &[range(3, 3), range(5, 5), range(10, 14), range(28, 30)],
+        );
+
+        db.delete(range(0, u64::MAX));
+        check(&db, &[]);
+        assert_eq!(db.get(3), None);
+    }
+
+    #[test]
+    fn test_replay() {
+        let script = "add 3-5\nadd 10-14\nadd 16-20\nadd 12-18\n\n\
+            # take one out of the middle\ndel 4-4\nquery 4\nquery 17\ncount\nlist\n";
+        let mut db = FreshDb::default();
+        let mut out = Vec::new();
+        db.replay(script.as_bytes(), &mut out).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "4 spoiled\n17 fresh (10-20)\n13\n3-3\n5-5\n10-20\n"
+        );
+
+        let error = db.replay("count\nremove 3-5\n".as_bytes(), &mut io::sink());
+        assert_eq!(
+            error.unwrap_err().to_string(),
+            "line 2: unknown command remove, expected add, del, query, count or list"
+        );
+        assert_eq!(
+            "query".parse::<Command>(),
+            Err(CommandError::MissingArgument)
+        );
+    }
+}
@@ -1,6 +1,21 @@
 use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::num::ParseIntError;
+use std::str::FromStr;
 
+use db::FreshDb;
+use provenance::{Provenance, Source};
 use rs_utils::input::read_input;
+use set::{FreshSet, Lookup};
+use weighted::WeightedSet;
+
+mod bench;
+mod db;
+mod provenance;
+mod set;
+mod stream;
+mod weighted;
 
 const TEST_INPUT: &str = "3-5
 10-14
@@ -27,18 +42,123 @@ impl fmt::Display for FreshRange {
     }
 }
 
-impl FreshRange {
-    fn from_str(s: &str) -> Self {
-        let parts: Vec<u64> = s.split("-").map(|p| p.parse().unwrap()).collect();
+/// Why a single range failed to parse.
+#[derive(Debug, PartialEq)]
+enum RangeError {
+    /// Not one of the accepted forms
+    Format,
+    /// One of the ends isn't a number, or doesn't fit in a u64
+    Number(ParseIntError),
+    /// A half-open range `a..b` with `b <= a`
+    Empty,
+}
 
-        if parts.len() != 2 {
-            panic!("FreshRange string is in wrong format {}", s);
+impl fmt::Display for RangeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Format => write!(f, "expected an ID, a-b, a- or a..b"),
+            Self::Number(e) => write!(f, "{}", e),
+            Self::Empty => write!(f, "half-open range contains no IDs"),
         }
+    }
+}
+
+fn parse_id(s: &str) -> Result<u64, RangeError> {
+    s.trim().parse().map_err(RangeError::Number)
+}
+
+impl FromStr for FreshRange {
+    type Err = RangeError;
+
+    /// Accepts `a-b` (either way round), a single ID `a`, the half-open
+    /// `a..b` which stops before `b`, and `a-` which runs to the top of u64.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+
+        if let Some((start, end)) = s.split_once("..") {
+            let (min, end) = (parse_id(start)?, parse_id(end)?);
+            if end <= min {
+                return Err(RangeError::Empty);
+            }
+            return Ok(FreshRange { min, max: end - 1 });
+        }
+
+        match s.split_once("-") {
+            None => {
+                let id = parse_id(s)?;
+                Ok(FreshRange { min: id, max: id })
+            }
+            Some((start, _)) if start.trim().is_empty() => Err(RangeError::Format),
+            Some((_, end)) if end.contains("-") => Err(RangeError::Format),
+            Some((start, end)) if end.trim().is_empty() => Ok(FreshRange {
+                min: parse_id(start)?,
+                max: u64::MAX,
+            }),
+            Some((start, end)) => {
+                let (a, b) = (parse_id(start)?, parse_id(end)?);
+                Ok(FreshRange {
+                    min: a.min(b),
+                    max: a.max(b),
+                })
+            }
+        }
+    }
+}
 
-        let min = *parts.iter().min().unwrap();
-        let max = *parts.iter().max().unwrap();
+/// Why the input failed to parse, with the line at fault.
+#[derive(Debug, PartialEq)]
+enum ParseError {
+    /// There was no ID section; `found` is how many sections there were
+    MissingIds { found: usize },
+    /// A third section without a header saying what it holds
+    ExtraSection { line: usize },
+    Range {
+        line: usize,
+        text: String,
+        error: RangeError,
+    },
+    Id {
+        line: usize,
+        text: String,
+        error: ParseIntError,
+    },
+    /// Something after a range that isn't `label=NAME` or `weight=N`
+    Attribute { line: usize, text: String },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingIds { found } => write!(
+                f,
+                "expected fresh ranges and IDs separated by a blank line, found {} section{}",
+                found,
+                if *found == 1 { "" } else { "s" }
+            ),
+            Self::ExtraSection { line } => write!(
+                f,
+                "line {}: unexpected third section, start it with [ranges] or [ids]",
+                line
+            ),
+            Self::Range { line, text, error } => {
+                write!(f, "line {}: bad range {:?}: {}", line, text, error)
+            }
+            Self::Id { line, text, error } => {
+                write!(f, "line {}: bad ID {:?}: {}", line, text, error)
+            }
+            Self::Attribute { line, text } => write!(
+                f,
+                "line {}: bad attribute {:?}, expected label=NAME or weight=N",
+                line, text
+            ),
+        }
+    }
+}
 
-        FreshRange { min, max }
+impl FreshRange {
+    /// Number of IDs covered. A u128 so the full-width range 0-u64::MAX fits.
+    fn len(&self) -> u128 {
+        (self.max - self.min) as u128 + 1
     }
 
     fn contains(&self, id: u64) -> bool {
@@ -61,71 +181,422 @@ impl FreshRange {
     }
 }
 
-fn parse(input: &str) -> (Vec<FreshRange>, Vec<u64>) {
-    let parts: Vec<&str> = input.trim().split("\n\n").collect();
+/// Parse a range line, which may end with `label=NAME` and `weight=N`.
+fn parse_source(line: usize, text: &str) -> Result<Source, ParseError> {
+    // Attributes start at the first word with an `=` in it
+    let (range_text, attributes) = match text.find('=') {
+        Some(eq) => {
+            let start = text[..eq].rfind(char::is_whitespace).map_or(0, |i| i + 1);
+            (text[..start].trim(), &text[start..])
+        }
+        None => (text, ""),
+    };
+
+    let range = range_text.parse().map_err(|error| ParseError::Range {
+        line,
+        text: range_text.to_string(),
+        error,
+    })?;
+    let mut source = Source {
+        line,
+        range,
+        label: None,
+        weight: 1,
+    };
+
+    for attribute in attributes.split_whitespace() {
+        match attribute.split_once('=') {
+            Some(("label", label)) if !label.is_empty() => {
+                source.label = Some(label.to_string());
+                continue;
+            }
+            Some(("weight", weight)) => {
+                if let Ok(weight) = weight.parse() {
+                    source.weight = weight;
+                    continue;
+                }
+            }
+            _ => {}
+        }
 
-    if parts.len() != 2 {
-        panic!("Input is not in correct format {}", input);
+        return Err(ParseError::Attribute {
+            line,
+            text: attribute.to_string(),
+        });
     }
 
-    let fresh_ranges: Vec<FreshRange> = parts[0].split("\n").map(FreshRange::from_str).collect();
-    let ids: Vec<u64> = parts[1].split("\n").map(|id| id.parse().unwrap()).collect();
+    Ok(source)
+}
 
-    (fresh_ranges, ids)
+/// What a section of the input holds.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Section {
+    Ranges,
+    Ids,
+    /// Headed with a name we don't know, skipped
+    Other,
 }
 
-fn part_1(input: &str) -> usize {
-    let mut count = 0;
-    let (fresh_ranges, ids) = parse(input);
+/// One entry read from the input.
+#[derive(Debug, PartialEq)]
+enum Entry {
+    Range(Source),
+    Id(u64),
+}
 
-    for id in ids {
-        let is_fresh = fresh_ranges.iter().any(|fr| fr.contains(id));
-        if is_fresh {
-            count += 1;
+/// Reads the input a line at a time, keeping track of which section it is in.
+///
+/// Sections are separated by blank lines. Unheaded sections are read as
+/// ranges then IDs; a section starting with a `[ranges]` or `[ids]` header
+/// adds to that list wherever it appears, and any other `[name]` section is
+/// ignored. `#` starts a comment, and a trailing `\r` is ignored.
+#[derive(Default)]
+struct SectionReader {
+    /// Ranges and IDs sections so far, headed or not. Ignored sections don't
+    /// count, so they can't change what an unheaded section holds.
+    sections: usize,
+    has_ids: bool,
+    /// `None` between sections
+    section: Option<Section>,
+}
+
+impl SectionReader {
+    /// Read line number `line`. Blank lines, comments, headers and lines in
+    /// ignored sections give `None`.
+    fn read_line(&mut self, line: usize, raw: &str) -> Result<Option<Entry>, ParseError> {
+        let text = raw.split('#').next().unwrap_or_default().trim();
+
+        if text.is_empty() {
+            // Only a truly blank line ends a section, not a comment
+            if raw.trim().is_empty() {
+                self.section = None;
+            }
+            return Ok(None);
+        }
+
+        if let Some(name) = text.strip_prefix('[').and_then(|t| t.strip_suffix(']')) {
+            let section = match name.trim() {
+                "ranges" => Section::Ranges,
+                "ids" => Section::Ids,
+                _ => Section::Other,
+            };
+            self.start(section);
+            return Ok(None);
+        }
+
+        let current = match self.section {
+            Some(current) => current,
+            None => {
+                let current = match self.sections {
+                    0 => Section::Ranges,
+                    1 => Section::Ids,
+                    _ => return Err(ParseError::ExtraSection { line }),
+                };
+                self.start(current);
+                current
+            }
+        };
+
+        match current {
+            Section::Ranges => Ok(Some(Entry::Range(parse_source(line, text)?))),
+            Section::Ids => {
+                let id = text.parse().map_err(|error| ParseError::Id {
+                    line,
+                    text: text.to_string(),
+                    error,
+                })?;
+                Ok(Some(Entry::Id(id)))
+            }
+            Section::Other => Ok(None),
+        }
+    }
+
+    fn start(&mut self, section: Section) {
+        self.section = Some(section);
+        if section != Section::Other {
+            self.sections += 1;
+        }
+        self.has_ids |= section == Section::Ids;
+    }
+
+    /// Check the input as a whole once every line has been read.
+    fn finish(&self) -> Result<(), ParseError> {
+        if !self.has_ids {
+            return Err(ParseError::MissingIds {
+                found: self.sections,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Parse the fresh ranges, with the line each came from, and the IDs.
+fn parse_sources(input: &str) -> Result<(Vec<Source>, Vec<u64>), ParseError> {
+    let mut sources = Vec::new();
+    let mut ids = Vec::new();
+    let mut reader = SectionReader::default();
+
+    for (i, raw) in input.lines().enumerate() {
+        match reader.read_line(i + 1, raw)? {
+            Some(Entry::Range(source)) => sources.push(source),
+            Some(Entry::Id(id)) => ids.push(id),
+            None => {}
         }
     }
+    reader.finish()?;
 
-    count
+    Ok((sources, ids))
 }
 
-fn part_2(input: &str) -> u64 {
-    let (mut fresh_ranges, _) = parse(input);
+fn parse(input: &str) -> Result<(Vec<FreshRange>, Vec<u64>), ParseError> {
+    let (sources, ids) = parse_sources(input)?;
+    Ok((sources.into_iter().map(|s| s.range).collect(), ids))
+}
+
+/// Parse an input that is expected to be valid, stopping with the error otherwise.
+fn parse_or_exit(input: &str) -> (Vec<FreshRange>, Vec<u64>) {
+    parse(input).unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// Whether ranges that touch without overlapping, like 3-5 and 6-11, are merged.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Merge {
+    Overlapping,
+    Adjacent,
+}
+
+/// Merge ranges into a sorted list of disjoint ranges in a single sweep.
+fn merge_ranges(mut fresh_ranges: Vec<FreshRange>, merge: Merge) -> Vec<FreshRange> {
+    // Sort ranges by min BEFORE merging so overlapping ranges become neighbours
+    fresh_ranges.sort_unstable_by_key(|r| r.min);
 
-    // Sort ranges by min BEFORE merging so overlapping ranges become adjacent
-    fresh_ranges.sort_by(|a, b| a.min.cmp(&b.min));
+    let mut merged: Vec<FreshRange> = Vec::with_capacity(fresh_ranges.len());
 
-    let mut continue_to_merge = true;
+    for range in fresh_ranges {
+        if let Some(last) = merged.last_mut() {
+            if let Ok(new_range) = last.combine(&range) {
+                *last = new_range;
+                continue;
+            }
+
+            if merge == Merge::Adjacent && last.max.checked_add(1) == Some(range.min) {
+                last.max = range.max;
+                continue;
+            }
+        }
+
+        merged.push(range);
+    }
+
+    merged
+}
+
+fn part_1(input: &str) -> usize {
+    let (fresh_ranges, ids) = parse_or_exit(input);
+
+    FreshSet::new(fresh_ranges).count_fresh(ids, Lookup::BinarySearch)
+}
 
-    while continue_to_merge {
-        let mut has_merged = false;
-        let mut i = 0;
+fn part_2(input: &str) -> u128 {
+    let (fresh_ranges, _) = parse_or_exit(input);
 
-        while i < fresh_ranges.len() - 1 {
-            let j = i + 1;
+    merge_ranges(fresh_ranges, Merge::Overlapping)
+        .iter()
+        .map(FreshRange::len)
+        .sum()
+}
 
-            let slf = fresh_ranges[i];
-            let other = fresh_ranges[j];
+/// Print the merged fresh ranges, one per line:
+/// `d05 merge [path] [--adjacent]`
+fn print_merged(args: &[String]) {
+    let path = args
+        .first()
+        .filter(|a| !a.starts_with("--"))
+        .map_or("./input.txt", String::as_str);
+    let merge = if args.iter().any(|a| a == "--adjacent") {
+        Merge::Adjacent
+    } else {
+        Merge::Overlapping
+    };
+
+    let (fresh_ranges, _) = parse_or_exit(&read_input(path));
+    for range in merge_ranges(fresh_ranges, merge) {
+        println!("{}-{}", range.min, range.max);
+    }
+}
 
-            let new = slf.combine(&other);
+/// Explain where fresh ranges came from: `d05 trace <path> [id...]`
+///
+/// With IDs, lists the input ranges covering each one. Without, lists every
+/// merged range and the input ranges folded into it.
+fn print_trace(args: &[String]) {
+    let path = args.first().expect("trace needs an input path");
+    let (sources, _) = parse_sources(&read_input(path)).unwrap_or_else(|e| panic!("{e}"));
+    let provenance = Provenance::new(sources, Merge::Overlapping);
+
+    if args.len() == 1 {
+        for group in provenance.groups() {
+            println!("{}-{}", group.range.min, group.range.max);
+            for source in &group.sources {
+                println!("    {}", source);
+            }
+        }
+        return;
+    }
 
-            if let Ok(new_range) = new {
-                has_merged = true;
-                fresh_ranges[i] = new_range;
-                fresh_ranges.remove(j);
+    for id in &args[1..] {
+        let id: u64 = id.parse().unwrap_or_else(|e| panic!("Bad ID {id}: {e}"));
+
+        match provenance.group(id) {
+            Some(group) => {
+                println!(
+                    "{} is fresh, in merged range {}-{}",
+                    id, group.range.min, group.range.max
+                );
+                for source in provenance.covering(id) {
+                    println!("    {}", source);
+                }
             }
+            None => println!("{} is spoiled", id),
+        }
+    }
+}
+
+/// Report the spoiled spans between fresh ranges:
+/// `d05 gaps <path> [--bounds min-max] [--top N]`
+///
+/// Bounds default to the lowest and highest fresh IDs.
+fn print_gaps(args: &[String]) {
+    let path = args.first().expect("gaps needs an input path");
+    let (fresh_ranges, ids) = parse_or_exit(&read_input(path));
+
+    let mut bounds = FreshRange {
+        min: fresh_ranges.iter().map(|r| r.min).min().unwrap_or(0),
+        max: fresh_ranges.iter().map(|r| r.max).max().unwrap_or(0),
+    };
+    let mut top = 10;
+
+    let mut flags = args[1..].iter();
+    while let Some(flag) = flags.next() {
+        let value = flags.next().expect("flag is missing a value");
+        match flag.as_str() {
+            "--bounds" => bounds = value.parse().unwrap_or_else(|e| panic!("{e}")),
+            "--top" => top = value.parse().unwrap_or_else(|e| panic!("{e}")),
+            f => panic!("Unknown flag {f}"),
+        }
+    }
+
+    let set = FreshSet::new(fresh_ranges);
+    let gaps = set.complement(bounds);
+    let spoiled: u128 = gaps.iter().map(FreshRange::len).sum();
+
+    println!(
+        "{} spoiled spans covering {} IDs in {}-{}",
+        gaps.len(),
+        spoiled,
+        bounds.min,
+        bounds.max
+    );
+    for gap in set.largest_gaps(bounds, top) {
+        println!("    {}-{} ({} IDs)", gap.min, gap.max, gap.len());
+    }
+    println!(
+        "{} of {} listed ingredients are spoiled",
+        set.count_spoiled(ids.clone(), Lookup::Sweep),
+        ids.len()
+    );
+}
+
+/// Resolve IDs against labelled, weighted ranges:
+/// `d05 weights <path> [id...]`
+///
+/// Prints the total weighted coverage, then which range each ID resolves to.
+/// With no IDs given, resolves the listed ingredients instead.
+fn print_weights(args: &[String]) {
+    let path = args.first().expect("weights needs an input path");
+    let (sources, listed) = parse_sources(&read_input(path)).unwrap_or_else(|e| panic!("{e}"));
+    let set = WeightedSet::new(sources);
+
+    let ids: Vec<u64> = if args.len() > 1 {
+        args[1..]
+            .iter()
+            .map(|id| id.parse().unwrap_or_else(|e| panic!("Bad ID {id}: {e}")))
+            .collect()
+    } else {
+        listed
+    };
+
+    println!("Weighted coverage {}", set.weighted_coverage());
+    for id in ids {
+        match set.top(id) {
+            Some(source) => println!("{} {} ({})", id, set.label(id).unwrap_or("-"), source),
+            None => println!("{} spoiled", id),
+        }
+    }
+}
 
-            i += 1;
+/// Count fresh IDs while streaming them from a file, or stdin if none is
+/// given: `d05 stream [path]`
+fn print_stream(args: &[String]) {
+    let count = match args.first() {
+        Some(path) => {
+            let file = File::open(path).unwrap_or_else(|e| panic!("{path}: {e}"));
+            stream::count_fresh(BufReader::new(file))
         }
+        None => stream::count_fresh(io::stdin().lock()),
+    }
+    .unwrap_or_else(|e| panic!("{e}"));
 
-        if !has_merged {
-            continue_to_merge = false;
+    println!("{} of {} IDs are fresh", count.fresh, count.ids);
+}
+
+/// Replay database commands from a script, or stdin if none is given:
+/// `d05 db [script] [--input path]`
+///
+/// `--input` starts the database from the fresh ranges of a puzzle input.
+fn run_db(args: &[String]) {
+    let mut script = None;
+    let mut db = FreshDb::default();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--input" => {
+                let path = args.next().expect("--input is missing a path");
+                db = FreshDb::new(parse_or_exit(&read_input(path)).0);
+            }
+            f if f.starts_with("--") => panic!("Unknown flag {f}"),
+            path => script = Some(path.to_string()),
         }
     }
 
-    fresh_ranges.iter().map(|r| r.max - r.min + 1).sum()
+    let out = &mut io::stdout().lock();
+    let result = match script {
+        Some(path) => {
+            let file = File::open(&path).unwrap_or_else(|e| panic!("{path}: {e}"));
+            db.replay(BufReader::new(file), out)
+        }
+        None => db.replay(io::stdin().lock(), out),
+    };
+
+    if let Err(e) = result {
+        panic!("{e}");
+    }
 }
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("bench") => return bench::run(&args[1..]),
+        Some("merge") => return print_merged(&args[1..]),
+        Some("trace") => return print_trace(&args[1..]),
+        Some("gaps") => return print_gaps(&args[1..]),
+        Some("db") => return run_db(&args[1..]),
+        Some("weights") => return print_weights(&args[1..]),
+        Some("stream") => return print_stream(&args[1..]),
+        _ => {}
+    }
+
     let input = read_input("./input.txt");
 
     println!("Part 1 test {}", part_1(TEST_INPUT));
@@ -149,6 +620,138 @@ mod tests {
         assert_eq!(part_2(TEST_INPUT), 14);
     }
 
+    #[test]
+    fn test_full_width() {
+        let input = "0-18446744073709551615\n5-6\n\n18446744073709551615";
+
+        assert_eq!(part_1(input), 1);
+        assert_eq!(part_2(input), 1 << 64);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            parse("3-5\n1").unwrap_err().to_string(),
+            "expected fresh ranges and IDs separated by a blank line, found 1 section"
+        );
+        assert_eq!(parse(""), Err(ParseError::MissingIds { found: 0 }));
+        assert_eq!(
+            parse("3-5\n\n1\n\n2"),
+            Err(ParseError::ExtraSection { line: 5 })
+        );
+
+        let too_big = parse("3-5\n4-18446744073709551616\n\n1").unwrap_err();
+        assert_eq!(
+            too_big.to_string(),
+            "line 2: bad range \"4-18446744073709551616\": number too large to fit in target type"
+        );
+
+        let not_range = parse("3-5-7\n\n1").unwrap_err();
+        assert!(matches!(
+            not_range,
+            ParseError::Range {
+                line: 1,
+                error: RangeError::Format,
+                ..
+            }
+        ));
+
+        assert_eq!(
+            parse("3-5 label=acme weight=heavy\n\n1")
+                .unwrap_err()
+                .to_string(),
+            "line 1: bad attribute \"weight=heavy\", expected label=NAME or weight=N"
+        );
+
+        let bad_id = parse("3-5\n\n1\n-2").unwrap_err();
+        assert!(matches!(bad_id, ParseError::Id { line: 4, .. }));
+    }
+
+    #[test]
+    fn test_range_syntax() {
+        let range = |s: &str| s.parse::<FreshRange>();
+
+        assert_eq!(range("7"), Ok(FreshRange { min: 7, max: 7 }));
+        assert_eq!(range("9-4"), Ok(FreshRange { min: 4, max: 9 }));
+        assert_eq!(range("3..6"), Ok(FreshRange { min: 3, max: 5 }));
+        assert_eq!(
+            range("12-"),
+            Ok(FreshRange {
+                min: 12,
+                max: u64::MAX
+            })
+        );
+        assert_eq!(range("6..6"), Err(RangeError::Empty));
+        assert_eq!(range("-4"), Err(RangeError::Format));
+    }
+
+    #[test]
+    fn test_richer_input() {
+        let input = "# Fresh ranges\r\n3-5\r\n10..15 # half-open\r\n16-20\r\n12-18\r\n\r\n\
+            1\r\n5\r\n8\r\n# listed later\r\n11\r\n\r\n\
+            [notes]\r\nnot parsed\r\n\r\n\
+            [ids]\r\n17\r\n32\r\n\r\n\
+            [ranges]\r\n40-\r\n";
+
+        let (sources, ids) = parse_sources(input).unwrap();
+        assert_eq!(sources[1].line, 3);
+        assert_eq!(sources[4].line, 21);
+        assert_eq!(ids, vec![1, 5, 8, 11, 17, 32]);
+
+        assert_eq!(part_1(input), 3);
+        assert_eq!(part_2(input), 14 + (u64::MAX - 40) as u128 + 1);
+
+        // Notes ahead of the data don't shift which section is which
+        let noted = "[notes]\nhello\n\n3-5\n\n4\n";
+        assert_eq!(part_1(noted), 1);
+        assert_eq!(
+            parse_sources(&format!("{noted}\n[notes]\nbye\n\n6")),
+            Err(ParseError::ExtraSection { line: 11 })
+        );
+    }
+
+    #[test]
+    fn test_merge_ranges() {
+        let (fresh_ranges, _) = parse(TEST_INPUT).unwrap();
+
+        assert_eq!(
+            merge_ranges(fresh_ranges, Merge::Overlapping),
+            vec![
+                FreshRange { min: 3, max: 5 },
+                FreshRange { min: 10, max: 20 }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merge_adjacent() {
+        let fresh_ranges = vec![
+            FreshRange { min: 6, max: 11 },
+            FreshRange { min: 3, max: 5 },
+            FreshRange { min: 13, max: 13 },
+            FreshRange { min: 12, max: 12 },
+            FreshRange {
+                min: 20,
+                max: u64::MAX,
+            },
+        ];
+
+        assert_eq!(
+            merge_ranges(fresh_ranges.clone(), Merge::Overlapping).len(),
+            5
+        );
+        assert_eq!(
+            merge_ranges(fresh_ranges, Merge::Adjacent),
+            vec![
+                FreshRange { min: 3, max: 13 },
+                FreshRange {
+                    min: 20,
+                    max: u64::MAX
+                }
+            ]
+        );
+    }
+
     #[test]
     fn test_combine_no_overlap() {
         let fresh_range_one = FreshRange { min: 3, max: 5 };
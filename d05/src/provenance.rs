@@ -0,0 +1,129 @@
+use std::fmt;
+
+use crate::{FreshRange, Merge, merge_ranges};
+
+/// An original range from the input and the line it was read from.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Source {
+    /// Line number in the input, counting from 1
+    pub line: usize,
+    pub range: FreshRange,
+    /// Who or what the range belongs to, e.g. a supplier
+    pub label: Option<String>,
+    /// Priority when ranges overlap, higher wins. Defaults to 1
+    pub weight: u32,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}: {}-{}",
+            self.line, self.range.min, self.range.max
+        )?;
+        if let Some(label) = &self.label {
+            write!(f, " label={}", label)?;
+        }
+        if self.weight != 1 {
+            write!(f, " weight={}", self.weight)?;
+        }
+        Ok(())
+    }
+}
+
+/// A merged range and the input ranges that were folded into it.
+#[derive(Debug, PartialEq)]
+pub struct Group {
+    pub range: FreshRange,
+    /// In input order
+    pub sources: Vec<Source>,
+}
+
+/// Merged ranges that remember where they came from, to explain why an ID
+/// is considered fresh.
+pub struct Provenance {
+    groups: Vec<Group>,
+}
+
+impl Provenance {
+    /// `sources` are expected in input order.
+    pub fn new(sources: Vec<Source>, merge: Merge) -> Self {
+        let ranges = sources.iter().map(|s| s.range).collect();
+        let mut groups: Vec<Group> = merge_ranges(ranges, merge)
+            .into_iter()
+            .map(|range| Group {
+                range,
+                sources: Vec::new(),
+            })
+            .collect();
+
+        // Every input range sits entirely inside exactly one merged range
+        for source in sources {
+            let g = groups.partition_point(|g| g.range.max < source.range.min);
+            groups[g].sources.push(source);
+        }
+
+        Provenance { groups }
+    }
+
+    pub fn groups(&self) -> &[Group] {
+        &self.groups
+    }
+
+    /// The merged range containing `id`, if it is fresh at all.
+    pub fn group(&self, id: u64) -> Option<&Group> {
+        let g = self.groups.partition_point(|g| g.range.max < id);
+        self.groups.get(g).filter(|g| g.range.contains(id))
+    }
+
+    /// Every input range that covers `id`, in input order.
+    pub fn covering(&self, id: u64) -> Vec<Source> {
+        self.group(id)
+            .map(|g| {
+                g.sources
+                    .iter()
+                    .filter(|s| s.range.contains(id))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{TEST_INPUT, parse_sources};
+
+    fn provenance() -> Provenance {
+        let (sources, _) = parse_sources(TEST_INPUT).unwrap();
+        Provenance::new(sources, Merge::Overlapping)
+    }
+
+    #[test]
+    fn test_covering() {
+        let provenance = provenance();
+        let lines = |id| -> Vec<usize> { provenance.covering(id).iter().map(|s| s.line).collect() };
+
+        assert_eq!(lines(5), vec![1]);
+        assert_eq!(lines(11), vec![2]);
+        assert_eq!(lines(17), vec![3, 4]);
+        assert!(lines(8).is_empty() && lines(32).is_empty());
+        assert_eq!(provenance.covering(17)[1].to_string(), "line 4: 12-18");
+    }
+
+    #[test]
+    fn test_groups() {
+        let provenance = provenance();
+        let groups = provenance.groups();
+
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].sources.len(), 1);
+        assert_eq!(groups[1].range, FreshRange { min: 10, max: 20 });
+        assert_eq!(
+            groups[1].sources.iter().map(|s| s.line).collect::<Vec<_>>(),
+            vec![2, 3, 4]
+        );
+        assert_eq!(provenance.group(14), Some(&groups[1]));
+    }
+}
@@ -0,0 +1,177 @@
+use crate::{FreshRange, Merge, merge_ranges};
+
+/// How a batch of IDs is checked against a `FreshSet`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Lookup {
+    /// Binary search the ranges for each ID
+    BinarySearch,
+    /// Sort the IDs, then walk them and the ranges together in one pass
+    Sweep,
+}
+
+/// Fresh ranges merged into sorted, disjoint spans for fast membership tests.
+pub struct FreshSet {
+    ranges: Vec<FreshRange>,
+}
+
+impl FreshSet {
+    pub fn new(fresh_ranges: Vec<FreshRange>) -> Self {
+        FreshSet {
+            ranges: merge_ranges(fresh_ranges, Merge::Adjacent),
+        }
+    }
+
+    pub fn contains(&self, id: u64) -> bool {
+        // The first range that doesn't end before `id` is the only candidate
+        let i = self.ranges.partition_point(|r| r.max < id);
+        self.ranges.get(i).is_some_and(|r| r.contains(id))
+    }
+
+    /// How many of `ids` fall in a fresh range, duplicates included.
+    pub fn count_fresh(&self, mut ids: Vec<u64>, lookup: Lookup) -> usize {
+        match lookup {
+            Lookup::BinarySearch => ids.iter().filter(|&&id| self.contains(id)).count(),
+            Lookup::Sweep => {
+                ids.sort_unstable();
+
+                let mut ranges = self.ranges.iter().peekable();
+                let mut count = 0;
+
+                for id in ids {
+                    while ranges.next_if(|r| r.max < id).is_some() {}
+
+                    match ranges.peek() {
+                        Some(r) if r.contains(id) => count += 1,
+                        Some(_) => {}
+                        None => break,
+                    }
+                }
+
+                count
+            }
+        }
+    }
+
+    /// How many of `ids` are spoiled, duplicates included.
+    pub fn count_spoiled(&self, ids: Vec<u64>, lookup: Lookup) -> usize {
+        let total = ids.len();
+        total - self.count_fresh(ids, lookup)
+    }
+
+    /// The spoiled spans within `bounds`, in order.
+    pub fn complement(&self, bounds: FreshRange) -> Vec<FreshRange> {
+        let mut gaps = Vec::new();
+        // Start of the next possible gap, `None` once past the top of u64
+        let mut cursor = Some(bounds.min);
+
+        let first = self.ranges.partition_point(|r| r.max < bounds.min);
+        for range in self.ranges[first..]
+            .iter()
+            .take_while(|r| r.min <= bounds.max)
+        {
+            let Some(start) = cursor else {
+                break;
+            };
+
+            if range.min > start {
+                gaps.push(FreshRange {
+                    min: start,
+                    max: range.min - 1,
+                });
+            }
+            cursor = range.max.checked_add(1);
+        }
+
+        if let Some(start) = cursor.filter(|&c| c <= bounds.max) {
+            gaps.push(FreshRange {
+                min: start,
+                max: bounds.max,
+            });
+        }
+
+        gaps
+    }
+
+    /// The `n` widest spoiled spans within `bounds`, widest first. Ties keep
+    /// the lower span first.
+    pub fn largest_gaps(&self, bounds: FreshRange, n: usize) -> Vec<FreshRange> {
+        let mut gaps = self.complement(bounds);
+        gaps.sort_by_key(|g| std::cmp::Reverse(g.max - g.min));
+        gaps.truncate(n);
+        gaps
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{TEST_INPUT, parse};
+
+    #[test]
+    fn test_contains() {
+        let (ranges, _) = parse(TEST_INPUT).unwrap();
+        let set = FreshSet::new(ranges);
+
+        assert!(set.contains(3) && set.contains(5) && set.contains(20));
+        assert!(!set.contains(2) && !set.contains(6) && !set.contains(21));
+    }
+
+    #[test]
+    fn test_lookups_agree() {
+        let (ranges, mut ids) = parse(TEST_INPUT).unwrap();
+        ids.extend([17, 0, 9, 10, 20, u64::MAX]);
+        let set = FreshSet::new(ranges);
+
+        assert_eq!(set.count_fresh(ids.clone(), Lookup::BinarySearch), 6);
+        assert_eq!(set.count_fresh(ids, Lookup::Sweep), 6);
+    }
+
+    #[test]
+    fn test_complement() {
+        let (ranges, ids) = parse(TEST_INPUT).unwrap();
+        let set = FreshSet::new(ranges);
+
+        assert_eq!(
+            set.complement(FreshRange { min: 0, max: 25 }),
+            vec![
+                FreshRange { min: 0, max: 2 },
+                FreshRange { min: 6, max: 9 },
+                FreshRange { min: 21, max: 25 },
+            ]
+        );
+        assert_eq!(
+            set.complement(FreshRange { min: 4, max: 12 }),
+            vec![FreshRange { min: 6, max: 9 }]
+        );
+        assert!(set.complement(FreshRange { min: 11, max: 12 }).is_empty());
+
+        assert_eq!(
+            set.largest_gaps(FreshRange { min: 0, max: 25 }, 2),
+            vec![
+                FreshRange { min: 21, max: 25 },
+                FreshRange { min: 6, max: 9 },
+            ]
+        );
+        assert_eq!(set.count_spoiled(ids, Lookup::Sweep), 3);
+    }
+
+    #[test]
+    fn test_complement_full_width() {
+        let set = FreshSet::new(vec![
+            FreshRange { min: 0, max: 9 },
+            FreshRange {
+                min: 20,
+                max: u64::MAX,
+            },
+        ]);
+        let everything = FreshRange {
+            min: 0,
+            max: u64::MAX,
+        };
+
+        assert_eq!(
+            set.complement(everything),
+            vec![FreshRange { min: 10, max: 19 }]
+        );
+    }
+}
@@ -0,0 +1,116 @@
+use std::fmt;
+use std::io::{self, BufRead};
+
+use crate::set::FreshSet;
+use crate::{Entry, FreshRange, ParseError, SectionReader};
+
+#[derive(Debug)]
+pub enum StreamError {
+    Io(io::Error),
+    Parse(ParseError),
+    /// Ranges have to come before the first ID when streaming
+    RangeAfterIds {
+        line: usize,
+    },
+}
+
+impl fmt::Display for StreamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{}", e),
+            Self::Parse(e) => write!(f, "{}", e),
+            Self::RangeAfterIds { line } => write!(
+                f,
+                "line {}: ranges must all come before the first ID when streaming",
+                line
+            ),
+        }
+    }
+}
+
+impl From<io::Error> for StreamError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<ParseError> for StreamError {
+    fn from(e: ParseError) -> Self {
+        Self::Parse(e)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct StreamCount {
+    pub ids: u64,
+    pub fresh: u64,
+}
+
+/// Count fresh IDs without holding them: only the ranges are kept, and each
+/// ID is checked and dropped as it is read, so memory doesn't grow with the
+/// number of IDs.
+pub fn count_fresh<R: BufRead>(mut reader: R) -> Result<StreamCount, StreamError> {
+    let mut sections = SectionReader::default();
+    let mut ranges: Vec<FreshRange> = Vec::new();
+    // Built from the ranges once the first ID turns up
+    let mut set: Option<FreshSet> = None;
+    let mut count = StreamCount { ids: 0, fresh: 0 };
+
+    let mut buffer = String::new();
+    let mut line = 0;
+
+    loop {
+        buffer.clear();
+        if reader.read_line(&mut buffer)? == 0 {
+            break;
+        }
+        line += 1;
+
+        match sections.read_line(line, &buffer)? {
+            Some(Entry::Range(source)) => {
+                if set.is_some() {
+                    return Err(StreamError::RangeAfterIds { line });
+                }
+                ranges.push(source.range);
+            }
+            Some(Entry::Id(id)) => {
+                let set = set.get_or_insert_with(|| FreshSet::new(std::mem::take(&mut ranges)));
+                count.ids += 1;
+                if set.contains(id) {
+                    count.fresh += 1;
+                }
+            }
+            None => {}
+        }
+    }
+    sections.finish()?;
+
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TEST_INPUT;
+
+    #[test]
+    fn test_matches_part_1() {
+        let count = count_fresh(TEST_INPUT.as_bytes()).unwrap();
+        assert_eq!(count, StreamCount { ids: 6, fresh: 3 });
+
+        let crlf = TEST_INPUT.replace('\n', "\r\n");
+        assert_eq!(count_fresh(crlf.as_bytes()).unwrap().fresh, 3);
+    }
+
+    #[test]
+    fn test_stream_errors() {
+        let late = count_fresh("3-5\n\n[ids]\n4\n\n[ranges]\n7-9\n".as_bytes());
+        assert!(matches!(late, Err(StreamError::RangeAfterIds { line: 7 })));
+
+        let missing = count_fresh("3-5\n".as_bytes()).unwrap_err();
+        assert!(matches!(
+            missing,
+            StreamError::Parse(ParseError::MissingIds { found: 1 })
+        ));
+    }
+}
@@ -0,0 +1,141 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use crate::FreshRange;
+use crate::provenance::Source;
+
+/// A run of IDs that all resolve to the same source.
+struct Segment {
+    range: FreshRange,
+    /// Index into `WeightedSet::sources`
+    source: usize,
+}
+
+/// Labelled, weighted ranges where overlaps are resolved rather than merged:
+/// each ID belongs to the heaviest range covering it, and among equally heavy
+/// ranges to the one listed first.
+pub struct WeightedSet {
+    sources: Vec<Source>,
+    /// Sorted and disjoint, so lookups are a binary search
+    segments: Vec<Segment>,
+}
+
+impl WeightedSet {
+    pub fn new(sources: Vec<Source>) -> Self {
+        // Every point where the set of covering ranges can change. u128 so a
+        // range ending at u64::MAX still has an end point.
+        let mut points: Vec<u128> = sources
+            .iter()
+            .flat_map(|s| [s.range.min as u128, s.range.max as u128 + 1])
+            .collect();
+        points.sort_unstable();
+        points.dedup();
+
+        let mut order: Vec<usize> = (0..sources.len()).collect();
+        order.sort_by_key(|&i| sources[i].range.min);
+        let mut order = order.into_iter().peekable();
+
+        // Ranges covering the current span, heaviest then earliest on top.
+        // Ranges that have ended are dropped lazily when they reach the top.
+        let mut active = BinaryHeap::new();
+        let mut segments: Vec<Segment> = Vec::new();
+
+        for span in points.windows(2) {
+            let (start, end) = (span[0], span[1]);
+
+            while let Some(i) = order.next_if(|&i| sources[i].range.min as u128 == start) {
+                active.push((sources[i].weight, Reverse(i)));
+            }
+            while let Some(&(_, Reverse(i))) = active.peek() {
+                if (sources[i].range.max as u128) >= start {
+                    break;
+                }
+                active.pop();
+            }
+
+            let Some(&(_, Reverse(source))) = active.peek() else {
+                continue;
+            };
+            let range = FreshRange {
+                min: start as u64,
+                max: (end - 1) as u64,
+            };
+
+            match segments.last_mut() {
+                Some(last) if last.source == source && last.range.max as u128 + 1 == start => {
+                    last.range.max = range.max
+                }
+                _ => segments.push(Segment { range, source }),
+            }
+        }
+
+        WeightedSet { sources, segments }
+    }
+
+    /// The range that `id` resolves to, if it is fresh.
+    pub fn top(&self, id: u64) -> Option<&Source> {
+        let i = self.segments.partition_point(|s| s.range.max < id);
+        self.segments
+            .get(i)
+            .filter(|s| s.range.contains(id))
+            .map(|s| &self.sources[s.source])
+    }
+
+    /// The label of the range that `id` resolves to, if it has one.
+    pub fn label(&self, id: u64) -> Option<&str> {
+        self.top(id).and_then(|s| s.label.as_deref())
+    }
+
+    /// The sum over every fresh ID of the weight it resolves to.
+    pub fn weighted_coverage(&self) -> u128 {
+        self.segments
+            .iter()
+            .map(|s| s.range.len() * self.sources[s.source].weight as u128)
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_sources;
+
+    const LABELLED: &str = "3-5 label=acme
+10-14 label=acme
+16-20 label=bulk weight=1
+12-18 label=premium weight=3
+0-2
+
+1
+";
+
+    fn set(input: &str) -> WeightedSet {
+        WeightedSet::new(parse_sources(input).unwrap().0)
+    }
+
+    #[test]
+    fn test_labels() {
+        let set = set(LABELLED);
+
+        assert_eq!(set.label(4), Some("acme"));
+        assert_eq!(set.label(11), Some("acme"));
+        assert_eq!(set.label(12), Some("premium"));
+        assert_eq!(set.label(18), Some("premium"));
+        assert_eq!(set.label(19), Some("bulk"));
+        assert_eq!(set.top(1).map(|s| s.line), Some(5));
+        assert_eq!(set.label(1), None);
+        assert!(set.top(7).is_none() && set.top(21).is_none());
+    }
+
+    #[test]
+    fn test_weighted_coverage() {
+        // 3 + 2 acme, 7 * 3 premium, 2 bulk, 3 unlabelled
+        assert_eq!(set(LABELLED).weighted_coverage(), 5 + 21 + 2 + 3);
+
+        // Equal weights resolve to the range listed first
+        let tied = set("0-10 label=first\n5-18446744073709551615 label=second\n\n1");
+        assert_eq!(tied.label(7), Some("first"));
+        assert_eq!(tied.label(u64::MAX), Some("second"));
+        assert_eq!(tied.weighted_coverage(), 1 << 64);
+    }
+}
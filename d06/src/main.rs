@@ -1,6 +1,17 @@
 use std::fmt::{self, Debug, Display};
+use std::io;
+use std::ops::Range;
 
+use number::{Number, Precision};
 use rs_utils::input::read_input;
+use worksheet::{Align, Layout};
+
+#[cfg(feature = "bignum")]
+use num_bigint::BigUint;
+
+mod number;
+mod report;
+mod worksheet;
 
 const TEST_INPUT: &str = "123 328  51 64
  45 64  387 23
@@ -8,42 +19,195 @@ const TEST_INPUT: &str = "123 328  51 64
 *   +   *   +
 ";
 
+#[derive(Clone, Copy, Debug, PartialEq)]
 enum Operation {
-    SUM,
-    MUL,
+    Sum,
+    Mul,
+    Sub,
+    /// Integer division, rounding down
+    Div,
+    Min,
+    Max,
+    /// Decimal digits written side by side, `12 | 34 = 1234`
+    Concat,
+    Pow,
+}
+
+impl Operation {
+    /// Combine two numbers, `a` being the one read first. `None` if the
+    /// answer doesn't fit in `N` or is undefined.
+    fn apply<N: Number>(&self, a: &N, b: &N) -> Option<N> {
+        match self {
+            Self::Sum => a.checked_add(b),
+            Self::Mul => a.checked_mul(b),
+            Self::Sub => a.checked_sub(b),
+            Self::Div => a.checked_div(b),
+            Self::Min => Some(a.min(b).clone()),
+            Self::Max => Some(a.max(b).clone()),
+            Self::Concat => a.checked_concat(b),
+            Self::Pow => a.checked_pow(b),
+        }
+    }
+
+    /// Exponentiation stacks from the right, `2 ^ 3 ^ 2 = 2 ^ 9`; everything
+    /// else folds from the left in the order the numbers are read.
+    fn is_right_associative(&self) -> bool {
+        *self == Self::Pow
+    }
+
+    /// Combine every number, or `None` if any step overflows or is undefined.
+    fn fold<N: Number>(&self, nums: Vec<N>) -> Option<N> {
+        let mut nums = nums.into_iter();
+
+        if self.is_right_associative() {
+            let last = nums.next_back()?;
+            nums.try_rfold(last, |acc, n| self.apply(&n, &acc))
+        } else {
+            let first = nums.next()?;
+            nums.try_fold(first, |acc, n| self.apply(&acc, &n))
+        }
+    }
+}
+
+impl TryFrom<char> for Operation {
+    type Error = String;
+
+    fn try_from(c: char) -> Result<Self, Self::Error> {
+        match c {
+            '+' => Ok(Self::Sum),
+            '*' => Ok(Self::Mul),
+            '-' => Ok(Self::Sub),
+            '/' => Ok(Self::Div),
+            '<' => Ok(Self::Min),
+            '>' => Ok(Self::Max),
+            '|' => Ok(Self::Concat),
+            '^' => Ok(Self::Pow),
+            o => Err(format!(
+                "Unexpected operation {o}, expected one of + * - / < > | ^"
+            )),
+        }
+    }
 }
 
 impl Display for Operation {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Self::SUM => write!(f, "+"),
-            Self::MUL => write!(f, "*"),
+            Self::Sum => write!(f, "+"),
+            Self::Mul => write!(f, "*"),
+            Self::Sub => write!(f, "-"),
+            Self::Div => write!(f, "/"),
+            Self::Min => write!(f, "<"),
+            Self::Max => write!(f, ">"),
+            Self::Concat => write!(f, "|"),
+            Self::Pow => write!(f, "^"),
         }
     }
 }
 
+/// Numbers in the order they were read, which matters for the operators
+/// that aren't commutative. They are kept as decimal digits so that each
+/// precision reads them in full, rather than after they have been squeezed
+/// into a fixed-width type.
+#[derive(PartialEq)]
 struct Equation {
-    nums: Vec<u64>,
+    nums: Vec<String>,
     op: Operation,
 }
 
 impl Equation {
-    fn solve(&self) -> u64 {
-        match self.op {
-            Operation::MUL => self.nums.iter().product(),
-            Operation::SUM => self.nums.iter().sum(),
+    /// The numbers in `N`, or the first one too large for it.
+    fn numbers<N: Number>(&self) -> Result<Vec<N>, &str> {
+        self.nums
+            .iter()
+            .map(|n| N::from_digits(n).ok_or(n.as_str()))
+            .collect()
+    }
+
+    /// Solve in `N`, or `None` if a number or any step overflows or is
+    /// undefined.
+    fn solve<N: Number>(&self) -> Option<N> {
+        self.op.fold(self.numbers().ok()?)
+    }
+}
+
+/// Why a worksheet couldn't be totalled.
+#[derive(Debug, PartialEq)]
+enum SolveError {
+    /// A problem, counting from 1 at the left, with a number that doesn't fit
+    Number {
+        index: usize,
+        number: String,
+        precision: &'static str,
+    },
+    /// A problem whose answer doesn't fit
+    Problem {
+        index: usize,
+        equation: String,
+        op: Operation,
+        precision: &'static str,
+    },
+    /// Every answer fits but their sum doesn't
+    Total { precision: &'static str },
+}
+
+impl Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Number {
+                index,
+                number,
+                precision,
+            } => write!(
+                f,
+                "problem {} has the number {}, too large for {}",
+                index, number, precision
+            ),
+            Self::Problem {
+                index,
+                equation,
+                op,
+                precision,
+            } => {
+                write!(f, "problem {} ({}) ", index, equation)?;
+                match op {
+                    Operation::Sub => write!(f, "goes below zero"),
+                    Operation::Div => write!(f, "divides by zero"),
+                    _ => write!(f, "overflows {}", precision),
+                }
+            }
+            Self::Total { precision } => write!(f, "grand total overflows {}", precision),
         }
     }
 }
 
+/// Add up the answers to every equation in `N`, stopping at the first one
+/// that can't be solved.
+fn total<N: Number>(equations: &[Equation]) -> Result<N, SolveError> {
+    equations
+        .iter()
+        .enumerate()
+        .try_fold(N::from_u64(0), |total, (i, e)| {
+            let nums = e.numbers::<N>().map_err(|number| SolveError::Number {
+                index: i + 1,
+                number: number.to_string(),
+                precision: N::NAME,
+            })?;
+            let answer = e.op.fold(nums).ok_or_else(|| SolveError::Problem {
+                index: i + 1,
+                equation: format!("{:?}", e),
+                op: e.op,
+                precision: N::NAME,
+            })?;
+
+            total
+                .checked_add(&answer)
+                .ok_or(SolveError::Total { precision: N::NAME })
+        })
+}
+
 impl Debug for Equation {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let s = self
-            .nums
-            .iter()
-            .map(|n| format!("{n}"))
-            .collect::<Vec<String>>()
-            .join(format!(" {} ", self.op).as_str());
+        let s = self.nums.join(format!(" {} ", self.op).as_str());
 
         write!(f, "{s}")
     }
@@ -54,6 +218,8 @@ impl Debug for Equation {
 struct Problem {
     /// The character grid (rows x cols), NOT including the operator row
     grid: Vec<Vec<char>>,
+    /// Columns of the worksheet the problem was read from
+    columns: Range<usize>,
     /// The operation for this problem
     op: Operation,
 }
@@ -135,32 +301,36 @@ fn create_problem(data_rows: &[Vec<char>], op_row: &[char], cols: &[usize]) -> P
         })
         .expect("No operator found for problem");
 
-    let op = match op_char {
-        '*' => Operation::MUL,
-        '+' => Operation::SUM,
-        o => panic!("Unexpected operation {o}"),
-    };
+    let op = Operation::try_from(op_char).unwrap_or_else(|e| panic!("{e}"));
+
+    Problem {
+        grid,
+        columns: cols[0]..cols[cols.len() - 1] + 1,
+        op,
+    }
+}
 
-    Problem { grid, op }
+/// The digits among `cells`, most significant first, without leading zeros.
+/// Cells with no digits at all read as 0.
+fn read_digits(cells: impl Iterator<Item = char>) -> String {
+    let digits: String = cells.filter(|c| c.is_ascii_digit()).collect();
+    match digits.trim_start_matches('0') {
+        "" => "0".to_string(),
+        trimmed => trimmed.to_string(),
+    }
 }
 
 /// Part 1: Read numbers left-to-right within each row
 fn problem_to_equation_part1(problem: &Problem) -> Equation {
-    let nums: Vec<u64> = problem
+    let nums: Vec<String> = problem
         .grid
         .iter()
-        .map(|row| {
-            let num_str: String = row.iter().filter(|c| c.is_ascii_digit()).collect();
-            num_str.parse().expect("Failed to parse number")
-        })
+        .map(|row| read_digits(row.iter().copied()))
         .collect();
 
     Equation {
         nums,
-        op: match problem.op {
-            Operation::SUM => Operation::SUM,
-            Operation::MUL => Operation::MUL,
-        },
+        op: problem.op,
     }
 }
 
@@ -170,63 +340,153 @@ fn problem_to_equation_part2(problem: &Problem) -> Equation {
     let row_count = problem.grid.len();
 
     // Process columns from right to left
-    let nums: Vec<u64> = (0..col_count)
+    let nums: Vec<String> = (0..col_count)
         .rev()
         .map(|col| {
-            // Build number from digits in this column
             // Top digit is most significant, bottom is least significant
-            // First collect all digits, then build the number
-            let digits: Vec<u64> = (0..row_count)
-                .filter_map(|row| {
-                    let ch = problem.grid[row][col];
-                    if ch.is_ascii_digit() {
-                        Some(ch.to_digit(10).unwrap() as u64)
-                    } else {
-                        None
-                    }
-                })
-                .collect();
-
-            // Build number: first digit is most significant
-            let mut num: u64 = 0;
-            for digit in digits {
-                num = num * 10 + digit;
-            }
-
-            num
+            read_digits((0..row_count).map(|row| problem.grid[row][col]))
         })
         .collect();
 
     Equation {
         nums,
-        op: match problem.op {
-            Operation::SUM => Operation::SUM,
-            Operation::MUL => Operation::MUL,
-        },
+        op: problem.op,
     }
 }
 
-fn part_1(input: &str) -> u64 {
-    let problems = parse_problems(input);
+/// Turns a problem into an equation under one of the two reading modes.
+type Reading = fn(&Problem) -> Equation;
 
-    problems
-        .iter()
-        .map(|p| problem_to_equation_part1(p))
-        .map(|e| e.solve())
-        .sum()
+fn equations(input: &str, read: Reading) -> Vec<Equation> {
+    parse_problems(input).iter().map(read).collect()
+}
+
+fn part_1(input: &str) -> u64 {
+    total(&equations(input, problem_to_equation_part1)).unwrap_or_else(|e| panic!("{e}"))
 }
 
 fn part_2(input: &str) -> u64 {
-    let problems = parse_problems(input);
+    total(&equations(input, problem_to_equation_part2)).unwrap_or_else(|e| panic!("{e}"))
+}
 
-    problems
-        .iter()
-        .map(|p| problem_to_equation_part2(p))
-        .map(|e| e.solve())
-        .sum()
+fn print_totals<N: Number>(input: &str) {
+    let parts: [(&str, Reading); 2] = [
+        ("Part 1", problem_to_equation_part1),
+        ("Part 2", problem_to_equation_part2),
+    ];
+
+    for (name, read) in parts {
+        match total::<N>(&equations(input, read)) {
+            Ok(total) => println!("{} {}", name, total),
+            Err(e) => println!("{} failed: {}", name, e),
+        }
+    }
+}
+
+/// Solve both parts with checked arithmetic in a chosen precision:
+/// `d06 solve <path> [--precision u64|u128|big]`
+fn solve(args: &[String]) {
+    let path = args.first().expect("solve needs an input path");
+    let precision = match args.get(1).map(String::as_str) {
+        Some("--precision") => args
+            .get(2)
+            .expect("--precision is missing a value")
+            .parse()
+            .unwrap_or_else(|e| panic!("{e}")),
+        Some(f) => panic!("Unknown flag {f}"),
+        None => Precision::U64,
+    };
+
+    let input = read_input(path);
+    match precision {
+        Precision::U64 => print_totals::<u64>(&input),
+        Precision::U128 => print_totals::<u128>(&input),
+        #[cfg(feature = "bignum")]
+        Precision::Big => print_totals::<BigUint>(&input),
+    }
+}
+
+/// Show each problem worked under both readings:
+/// `d06 work <path> [--grid]`
+///
+/// `--grid` also prints the worksheet with the answers written under it.
+fn show_work(args: &[String]) {
+    let path = args.first().expect("work needs an input path");
+    let grid = match args.get(1).map(String::as_str) {
+        Some("--grid") => true,
+        Some(f) => panic!("Unknown flag {f}"),
+        None => false,
+    };
+
+    let input = read_input(path);
+    let problems = parse_problems(&input);
+    let out = &mut io::stdout().lock();
+
+    report::write_table(out, &problems).expect("Failed to write report");
+    if grid {
+        println!();
+        report::write_annotated(out, &input, &problems).expect("Failed to write worksheet");
+    }
+}
+
+/// Write a random worksheet to stdout:
+/// `d06 generate [--problems N] [--numbers N] [--digits N] [--ops +*-/<>|^]
+/// [--layout rows|columns] [--align left|right] [--seed N]`
+fn generate(args: &[String]) {
+    let mut problems = 10;
+    let mut numbers = 4;
+    let mut digits = 4;
+    let mut ops = vec![Operation::Sum, Operation::Mul];
+    let mut layout = Layout::Rows;
+    let mut align = Align::Right;
+    let mut seed = 1;
+
+    let mut flags = args.iter();
+    while let Some(flag) = flags.next() {
+        let value = flags.next().expect("flag is missing a value");
+        match flag.as_str() {
+            "--problems" => problems = value.parse().unwrap_or_else(|e| panic!("{e}")),
+            "--numbers" => numbers = value.parse().unwrap_or_else(|e| panic!("{e}")),
+            "--digits" => digits = value.parse().unwrap_or_else(|e| panic!("{e}")),
+            "--ops" => {
+                ops = value
+                    .chars()
+                    .map(|c| Operation::try_from(c).unwrap_or_else(|e| panic!("{e}")))
+                    .collect()
+            }
+            "--layout" => layout = value.parse().unwrap_or_else(|e| panic!("{e}")),
+            "--align" => align = value.parse().unwrap_or_else(|e| panic!("{e}")),
+            "--seed" => seed = value.parse().unwrap_or_else(|e| panic!("{e}")),
+            f => panic!("Unknown flag {f}"),
+        }
+    }
+
+    assert!(
+        digits >= 1 && !ops.is_empty(),
+        "Need at least one digit and one operator"
+    );
+
+    let generated = worksheet::random_equations(problems, numbers, digits, &ops, seed);
+    let worksheet =
+        worksheet::write_worksheet(&generated, layout, align).unwrap_or_else(|e| panic!("{e}"));
+
+    // Generated data is only useful if it reads back as what was generated
+    assert!(
+        equations(&worksheet, layout.reading()) == generated,
+        "Worksheet doesn't read back as the generated equations"
+    );
+    print!("{}", worksheet);
 }
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("solve") => return solve(&args[1..]),
+        Some("work") => return show_work(&args[1..]),
+        Some("generate") => return generate(&args[1..]),
+        _ => {}
+    }
+
     let input = read_input("./input.txt");
 
     println!("Part 1 test {}", part_1(TEST_INPUT));
@@ -249,4 +509,106 @@ mod tests {
     fn test_part_2() {
         assert_eq!(part_2(TEST_INPUT), 3263827);
     }
+
+    const MIXED_INPUT: &str = "12 9 20 2 5 7
+ 4 2  5 3 2 3
+-  / |  ^ < >
+";
+
+    #[test]
+    fn test_mixed_operators() {
+        // 12 - 4, 9 / 2, 20 | 5, 2 ^ 3, min(5, 2), max(7, 3)
+        assert_eq!(part_1(MIXED_INPUT), 8 + 4 + 205 + 8 + 2 + 7);
+        // 24 - 1, 92, 5 | 2, 23, 52, 73
+        assert_eq!(part_2(MIXED_INPUT), 23 + 92 + 52 + 23 + 52 + 73);
+    }
+
+    #[test]
+    fn test_fold_order() {
+        let solve = |op, nums: &[u64]| {
+            Equation {
+                nums: nums.iter().map(u64::to_string).collect(),
+                op,
+            }
+            .solve::<u64>()
+            .unwrap()
+        };
+
+        assert_eq!(solve(Operation::Sub, &[20, 5, 3]), 12);
+        assert_eq!(solve(Operation::Div, &[100, 5, 3]), 6);
+        assert_eq!(solve(Operation::Pow, &[2, 3, 2]), 512);
+        assert_eq!(solve(Operation::Concat, &[12, 0, 345]), 120345);
+        assert_eq!(solve(Operation::Min, &[7]), 7);
+        assert!(Operation::try_from('%').is_err());
+    }
+
+    const TALL_INPUT: &str = "1 99999
+1 99999
+1 99999
+1 99999
+1 99999
++ *
+";
+
+    #[test]
+    fn test_checked_totals() {
+        let tall = equations(TALL_INPUT, problem_to_equation_part1);
+
+        assert_eq!(
+            total::<u64>(&tall).unwrap_err().to_string(),
+            "problem 2 (99999 * 99999 * 99999 * 99999 * 99999) overflows u64"
+        );
+        assert_eq!(total::<u128>(&tall), Ok(5 + 99999u128.pow(5)));
+
+        let below_zero = equations("3\n5\n-", problem_to_equation_part1);
+        assert!(matches!(
+            total::<u128>(&below_zero),
+            Err(SolveError::Problem { index: 1, .. })
+        ));
+
+        let huge = [u64::MAX, u64::MAX].map(|n| Equation {
+            nums: vec![n.to_string()],
+            op: Operation::Sum,
+        });
+        assert_eq!(
+            total::<u64>(&huge),
+            Err(SolveError::Total { precision: "u64" })
+        );
+    }
+
+    #[test]
+    fn test_tall_numbers() {
+        // 21 rows, so each column reads as a 21 digit number
+        let tall = "9 1\n".repeat(21) + "+ +\n";
+        let columns = equations(&tall, problem_to_equation_part2);
+
+        assert_eq!(
+            total::<u64>(&columns),
+            Err(SolveError::Number {
+                index: 1,
+                number: "9".repeat(21),
+                precision: "u64"
+            })
+        );
+        assert_eq!(
+            total::<u128>(&columns),
+            Ok(10u128.pow(21) - 1 + "1".repeat(21).parse::<u128>().unwrap())
+        );
+        assert_eq!(
+            total::<u128>(&equations(&tall, problem_to_equation_part1)),
+            Ok(210)
+        );
+
+        // A 25 digit row is too wide for u64 but not for u128
+        let wide = equations(
+            "1 0025\n2 9999999999999999999999999\n+ +\n",
+            problem_to_equation_part1,
+        );
+        assert_eq!(wide[1].nums[0], "25");
+        assert!(matches!(
+            total::<u64>(&wide),
+            Err(SolveError::Number { index: 2, .. })
+        ));
+        assert_eq!(total::<u128>(&wide), Ok(3 + 25 + 10u128.pow(25) - 1));
+    }
 }
@@ -0,0 +1,203 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+#[cfg(feature = "bignum")]
+use num_bigint::BigUint;
+
+/// An integer type equations can be solved in. Every operation is checked,
+/// giving `None` where the answer doesn't fit or isn't defined.
+pub trait Number: Sized + Clone + Ord + Display {
+    /// Shown in errors
+    const NAME: &'static str;
+
+    fn from_u64(n: u64) -> Self;
+    fn checked_add(&self, other: &Self) -> Option<Self>;
+    fn checked_sub(&self, other: &Self) -> Option<Self>;
+    fn checked_mul(&self, other: &Self) -> Option<Self>;
+    fn checked_div(&self, other: &Self) -> Option<Self>;
+    fn checked_pow(&self, exp: &Self) -> Option<Self>;
+    /// Number of decimal digits, 1 for zero
+    fn digits(&self) -> u32;
+
+    /// Read a string of decimal digits, `None` if it doesn't fit.
+    fn from_digits(digits: &str) -> Option<Self> {
+        let ten = Self::from_u64(10);
+        digits.chars().try_fold(Self::from_u64(0), |n, c| {
+            let digit = Self::from_u64(c.to_digit(10)? as u64);
+            n.checked_mul(&ten)?.checked_add(&digit)
+        })
+    }
+
+    /// The digits of `other` written after those of `self`.
+    fn checked_concat(&self, other: &Self) -> Option<Self> {
+        Self::from_u64(10)
+            .checked_pow(&Self::from_u64(other.digits() as u64))
+            .and_then(|shift| self.checked_mul(&shift))
+            .and_then(|n| n.checked_add(other))
+    }
+}
+
+macro_rules! impl_number {
+    ($t:ty) => {
+        impl Number for $t {
+            const NAME: &'static str = stringify!($t);
+
+            fn from_u64(n: u64) -> Self {
+                n as $t
+            }
+
+            fn checked_add(&self, other: &Self) -> Option<Self> {
+                <$t>::checked_add(*self, *other)
+            }
+
+            fn checked_sub(&self, other: &Self) -> Option<Self> {
+                <$t>::checked_sub(*self, *other)
+            }
+
+            fn checked_mul(&self, other: &Self) -> Option<Self> {
+                <$t>::checked_mul(*self, *other)
+            }
+
+            fn checked_div(&self, other: &Self) -> Option<Self> {
+                <$t>::checked_div(*self, *other)
+            }
+
+            fn checked_pow(&self, exp: &Self) -> Option<Self> {
+                match u32::try_from(*exp) {
+                    Ok(exp) => <$t>::checked_pow(*self, exp),
+                    // Only 0 and 1 stay in range for exponents this large
+                    Err(_) => (*self <= 1).then_some(*self),
+                }
+            }
+
+            fn digits(&self) -> u32 {
+                self.checked_ilog10().unwrap_or(0) + 1
+            }
+        }
+    };
+}
+
+impl_number!(u64);
+impl_number!(u128);
+
+/// The most bits a big integer power may take, 8 MiB or about 20 million
+/// decimal digits. Beyond this an answer is treated as overflowing rather
+/// than left to run out of memory.
+#[cfg(feature = "bignum")]
+const MAX_POW_BITS: u64 = 1 << 26;
+
+#[cfg(feature = "bignum")]
+impl Number for BigUint {
+    const NAME: &'static str = "big integers";
+
+    fn from_u64(n: u64) -> Self {
+        BigUint::from(n)
+    }
+
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        Some(self + other)
+    }
+
+    fn checked_sub(&self, other: &Self) -> Option<Self> {
+        (self >= other).then(|| self - other)
+    }
+
+    fn checked_mul(&self, other: &Self) -> Option<Self> {
+        Some(self * other)
+    }
+
+    fn checked_div(&self, other: &Self) -> Option<Self> {
+        (*other != BigUint::ZERO).then(|| self / other)
+    }
+
+    /// Answers are capped at `MAX_POW_BITS`, judged from the base's size
+    /// before any work is done. 0 and 1 stay as they are for any exponent.
+    fn checked_pow(&self, exp: &Self) -> Option<Self> {
+        if *self <= BigUint::from(1u64) {
+            return Some(if *exp == BigUint::ZERO {
+                BigUint::from(1u64)
+            } else {
+                self.clone()
+            });
+        }
+
+        let exp = u32::try_from(exp).ok()?;
+        let bits = self.bits().checked_mul(exp as u64)?;
+        (bits <= MAX_POW_BITS).then(|| self.pow(exp))
+    }
+
+    fn digits(&self) -> u32 {
+        self.to_string().len() as u32
+    }
+}
+
+/// Which integer type to solve in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Precision {
+    U64,
+    U128,
+    #[cfg(feature = "bignum")]
+    Big,
+}
+
+impl FromStr for Precision {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "u64" => Ok(Self::U64),
+            "u128" => Ok(Self::U128),
+            #[cfg(feature = "bignum")]
+            "big" => Ok(Self::Big),
+            #[cfg(feature = "bignum")]
+            p => Err(format!("Unknown precision {p}, expected u64, u128 or big")),
+            #[cfg(not(feature = "bignum"))]
+            p => Err(format!(
+                "Unknown precision {p}, expected u64 or u128 (big needs the bignum feature)"
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checked() {
+        assert_eq!(Number::checked_add(&u64::MAX, &1), None);
+        assert_eq!(Number::checked_sub(&3u64, &4), None);
+        assert_eq!(Number::checked_div(&3u64, &0), None);
+        assert_eq!(Number::checked_pow(&2u64, &64), None);
+        assert_eq!(Number::checked_pow(&2u128, &64), Some(1 << 64));
+        assert_eq!(Number::checked_pow(&1u64, &(1 << 40)), Some(1));
+        assert_eq!(12u64.checked_concat(&0), Some(120));
+        assert_eq!(12u64.checked_concat(&345), Some(12345));
+        assert_eq!(u64::MAX.checked_concat(&1), None);
+        assert_eq!(u64::from_digits("18446744073709551615"), Some(u64::MAX));
+        assert_eq!(u64::from_digits("18446744073709551616"), None);
+        assert_eq!(u128::from_digits("18446744073709551616"), Some(1 << 64));
+    }
+
+    #[cfg(feature = "bignum")]
+    #[test]
+    fn test_big() {
+        let big = BigUint::from(u128::MAX);
+        let square = big.checked_mul(&big).unwrap();
+
+        assert_eq!(square.digits(), 78);
+        assert_eq!(square.checked_div(&big), Some(big.clone()));
+        assert_eq!(BigUint::from(1u64).checked_sub(&big), None);
+        assert_eq!(BigUint::from(1u64).checked_pow(&square), Some(1u64.into()));
+        assert_eq!(BigUint::ZERO.checked_pow(&BigUint::ZERO), Some(1u64.into()));
+
+        // Too large to build, without trying to
+        let nine = BigUint::from(9u64);
+        assert_eq!(nine.checked_pow(&u32::MAX.into()), None);
+        assert_eq!(nine.checked_pow(&square), None);
+        assert_eq!(
+            BigUint::from(2u64).checked_pow(&(1u64 << 20).into()),
+            Some(BigUint::from(1u64) << (1usize << 20))
+        );
+    }
+}
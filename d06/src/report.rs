@@ -0,0 +1,196 @@
+use std::io::{self, Write};
+
+use crate::{Equation, Problem, problem_to_equation_part1, problem_to_equation_part2};
+
+/// An answer worked out in u128, so the report doesn't stop at the first
+/// problem too big for u64.
+fn answer(equation: &Equation) -> String {
+    equation
+        .solve::<u128>()
+        .map_or("overflow".to_string(), |a| a.to_string())
+}
+
+fn numbers(equation: &Equation) -> String {
+    equation.nums.join(" ")
+}
+
+/// A table of every problem: where it sits on the worksheet, its operator,
+/// and the numbers and answer under each reading.
+pub fn write_table<W: Write>(w: &mut W, problems: &[Problem]) -> io::Result<()> {
+    let header = [
+        "Problem", "Span", "Op", "Rows", "Answer", "Columns", "Answer",
+    ]
+    .map(String::from);
+
+    let body: Vec<Vec<String>> = problems
+        .iter()
+        .enumerate()
+        .map(|(i, problem)| {
+            let rows = problem_to_equation_part1(problem);
+            let columns = problem_to_equation_part2(problem);
+
+            vec![
+                (i + 1).to_string(),
+                format!("{}-{}", problem.columns.start, problem.columns.end - 1),
+                problem.op.to_string(),
+                numbers(&rows),
+                answer(&rows),
+                numbers(&columns),
+                answer(&columns),
+            ]
+        })
+        .collect();
+
+    // Numbers to the right, text to the left
+    let right = [true, false, false, false, true, false, true];
+    write_aligned(w, &header, &body, &right)
+}
+
+fn write_aligned<W: Write>(
+    w: &mut W,
+    header: &[String],
+    body: &[Vec<String>],
+    right: &[bool],
+) -> io::Result<()> {
+    let widths: Vec<usize> = (0..header.len())
+        .map(|col| {
+            body.iter()
+                .map(|row| row[col].len())
+                .chain(std::iter::once(header[col].len()))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let write_row = |w: &mut W, cells: &[String]| -> io::Result<()> {
+        let line = cells
+            .iter()
+            .zip(&widths)
+            .zip(right)
+            .map(|((cell, &width), &right)| {
+                if right {
+                    format!("{cell:>width$}")
+                } else {
+                    format!("{cell:<width$}")
+                }
+            })
+            .collect::<Vec<String>>()
+            .join(" | ");
+        writeln!(w, "{}", line.trim_end())
+    };
+
+    write_row(w, header)?;
+    let rule = widths
+        .iter()
+        .map(|&width| "-".repeat(width))
+        .collect::<Vec<String>>()
+        .join("-+-");
+    writeln!(w, "{}", rule)?;
+    for row in body {
+        write_row(w, row)?;
+    }
+
+    Ok(())
+}
+
+/// Lay out `answers` on as few lines as possible, each starting at the left
+/// of its problem's columns, moving down a line wherever it would run into
+/// the answer before it.
+fn pack_answers(problems: &[Problem], answers: &[String]) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+
+    for (problem, answer) in problems.iter().zip(answers) {
+        let start = problem.columns.start;
+        // Keep at least one space after whatever is already on the line
+        let line = match lines
+            .iter_mut()
+            .find(|l| l.is_empty() || l.chars().count() < start)
+        {
+            Some(line) => line,
+            None => {
+                lines.push(String::new());
+                lines.last_mut().unwrap()
+            }
+        };
+
+        let pad = start - line.chars().count();
+        line.push_str(&" ".repeat(pad));
+        line.push_str(answer);
+    }
+
+    lines
+}
+
+/// The original worksheet with each problem's answers written underneath:
+/// a rule under each problem, then the answers reading rows, then another
+/// rule and the answers reading columns.
+pub fn write_annotated<W: Write>(w: &mut W, input: &str, problems: &[Problem]) -> io::Result<()> {
+    for line in input.lines() {
+        writeln!(w, "{}", line.trim_end())?;
+    }
+
+    let width = problems.last().map_or(0, |p| p.columns.end);
+    let mut rule = vec![' '; width];
+    for problem in problems {
+        rule[problem.columns.clone()].fill('-');
+    }
+    let rule: String = rule.into_iter().collect();
+
+    let readings = [problem_to_equation_part1, problem_to_equation_part2];
+    for read in readings {
+        let answers: Vec<String> = problems.iter().map(|p| answer(&read(p))).collect();
+
+        writeln!(w, "{}", rule)?;
+        for line in pack_answers(problems, &answers) {
+            writeln!(w, "{}", line)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{TEST_INPUT, parse_problems};
+
+    fn render(f: impl Fn(&mut Vec<u8>, &[Problem]) -> io::Result<()>) -> String {
+        let mut out = Vec::new();
+        f(&mut out, &parse_problems(TEST_INPUT)).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_table() {
+        assert_eq!(
+            render(write_table),
+            "\
+Problem | Span  | Op | Rows       |  Answer | Columns    |  Answer
+--------+-------+----+------------+---------+------------+--------
+      1 | 0-2   | *  | 123 45 6   |   33210 | 356 24 1   |    8544
+      2 | 4-6   | +  | 328 64 98  |     490 | 8 248 369  |     625
+      3 | 8-10  | *  | 51 387 215 | 4243455 | 175 581 32 | 3253600
+      4 | 12-14 | +  | 64 23 314  |     401 | 4 431 623  |    1058
+"
+        );
+    }
+
+    #[test]
+    fn test_annotated() {
+        assert_eq!(
+            render(|w, p| write_annotated(w, TEST_INPUT, p)),
+            "\
+123 328  51 64
+ 45 64  387 23
+  6 98  215 314
+*   +   *   +
+--- --- --- ---
+33210   4243455
+    490     401
+--- --- --- ---
+8544    3253600
+    625     1058
+"
+        );
+    }
+}
@@ -0,0 +1,272 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{Equation, Operation, Reading, problem_to_equation_part1, problem_to_equation_part2};
+
+/// Which way the numbers of a problem are written down.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Layout {
+    /// One number per row, read left to right as in part 1
+    Rows,
+    /// One number per column, read top to bottom and right to left as in part 2
+    Columns,
+}
+
+impl Layout {
+    /// How a worksheet in this layout is read back.
+    pub fn reading(&self) -> Reading {
+        match self {
+            Self::Rows => problem_to_equation_part1,
+            Self::Columns => problem_to_equation_part2,
+        }
+    }
+}
+
+impl FromStr for Layout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rows" => Ok(Self::Rows),
+            "columns" => Ok(Self::Columns),
+            l => Err(format!("Unknown layout {l}, expected rows or columns")),
+        }
+    }
+}
+
+/// Where the digits of a number sit in the space given to it. In the columns
+/// layout left means at the top and right at the bottom.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Align {
+    Left,
+    Right,
+}
+
+impl FromStr for Align {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "left" => Ok(Self::Left),
+            "right" => Ok(Self::Right),
+            a => Err(format!("Unknown alignment {a}, expected left or right")),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum LayoutError {
+    /// An equation, counting from 1, with nothing to write
+    Empty { index: usize },
+    /// In the rows layout every problem shares the same rows, so needs the
+    /// same number of numbers
+    Ragged {
+        index: usize,
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty { index } => write!(f, "equation {} has no numbers", index),
+            Self::Ragged {
+                index,
+                expected,
+                found,
+            } => write!(
+                f,
+                "equation {} has {} numbers but the rows layout needs {} like the first",
+                index, found, expected
+            ),
+        }
+    }
+}
+
+/// Lay `equations` out as a worksheet that reads back as the same equations
+/// under `layout`'s reading. Problems are separated by a single blank
+/// column, with the operator under the first column of each.
+pub fn write_worksheet(
+    equations: &[Equation],
+    layout: Layout,
+    align: Align,
+) -> Result<String, LayoutError> {
+    if let Some(i) = equations.iter().position(|e| e.nums.is_empty()) {
+        return Err(LayoutError::Empty { index: i + 1 });
+    }
+
+    let digits: Vec<Vec<String>> = equations.iter().map(|e| e.nums.clone()).collect();
+
+    let height = match layout {
+        Layout::Rows => {
+            let expected = equations.first().map_or(0, |e| e.nums.len());
+            if let Some(i) = equations.iter().position(|e| e.nums.len() != expected) {
+                return Err(LayoutError::Ragged {
+                    index: i + 1,
+                    expected,
+                    found: equations[i].nums.len(),
+                });
+            }
+            expected
+        }
+        Layout::Columns => digits.iter().flatten().map(String::len).max().unwrap_or(0),
+    };
+
+    let widths: Vec<usize> = digits
+        .iter()
+        .map(|nums| match layout {
+            Layout::Rows => nums.iter().map(String::len).max().unwrap_or(0),
+            Layout::Columns => nums.len(),
+        })
+        .collect();
+    let width = widths.iter().sum::<usize>() + widths.len().saturating_sub(1);
+
+    let mut grid = vec![vec![' '; width]; height + 1];
+    let mut start = 0;
+
+    for ((equation, nums), &problem_width) in equations.iter().zip(&digits).zip(&widths) {
+        for (i, num) in nums.iter().enumerate() {
+            // Where the number starts along the row or down the column
+            let gap = match layout {
+                Layout::Rows => problem_width,
+                Layout::Columns => height,
+            } - num.len();
+            let offset = if align == Align::Right { gap } else { 0 };
+
+            for (d, digit) in num.chars().enumerate() {
+                match layout {
+                    Layout::Rows => grid[i][start + offset + d] = digit,
+                    // The first number read is the rightmost column
+                    Layout::Columns => grid[offset + d][start + problem_width - 1 - i] = digit,
+                }
+            }
+        }
+
+        grid[height][start] = equation.op.to_string().chars().next().unwrap();
+        start += problem_width + 1;
+    }
+
+    let mut worksheet = String::new();
+    for row in grid {
+        let line: String = row.into_iter().collect();
+        worksheet.push_str(line.trim_end());
+        worksheet.push('\n');
+    }
+
+    Ok(worksheet)
+}
+
+/// Equations with random numbers of 1 to `max_digits` digits and operators
+/// drawn from `ops`, from a seeded xorshift generator so runs repeat.
+pub fn random_equations(
+    count: usize,
+    numbers: usize,
+    max_digits: u32,
+    ops: &[Operation],
+    seed: u64,
+) -> Vec<Equation> {
+    let mut state = seed.max(1);
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+
+    (0..count)
+        .map(|_| {
+            let nums = (0..numbers)
+                .map(|_| {
+                    let digits = 1 + (next() % max_digits as u64) as u32;
+                    (next() % 10u64.pow(digits)).to_string()
+                })
+                .collect();
+            let op = ops[next() as usize % ops.len()];
+
+            Equation { nums, op }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{TEST_INPUT, equations};
+
+    const ALL_OPS: [Operation; 8] = [
+        Operation::Sum,
+        Operation::Mul,
+        Operation::Sub,
+        Operation::Div,
+        Operation::Min,
+        Operation::Max,
+        Operation::Concat,
+        Operation::Pow,
+    ];
+
+    fn round_trip(original: &[Equation], layout: Layout, align: Align) {
+        let worksheet = write_worksheet(original, layout, align).unwrap();
+        assert_eq!(equations(&worksheet, layout.reading()), original);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let example = equations(TEST_INPUT, problem_to_equation_part1);
+        let random = random_equations(40, 5, 6, &ALL_OPS, 99);
+
+        for layout in [Layout::Rows, Layout::Columns] {
+            for align in [Align::Left, Align::Right] {
+                round_trip(&example, layout, align);
+                round_trip(&random, layout, align);
+            }
+        }
+
+        // Columns can hold problems of different sizes
+        let ragged = equations(TEST_INPUT, problem_to_equation_part2)
+            .into_iter()
+            .chain(random_equations(3, 2, 3, &ALL_OPS, 5))
+            .collect::<Vec<_>>();
+        round_trip(&ragged, Layout::Columns, Align::Right);
+    }
+
+    #[test]
+    fn test_layouts() {
+        let example = equations(TEST_INPUT, problem_to_equation_part1);
+
+        assert_eq!(
+            write_worksheet(&example, Layout::Rows, Align::Right).unwrap(),
+            "123 328  51  64\n 45  64 387  23\n  6  98 215 314\n*   +   *   +\n"
+        );
+        assert_eq!(
+            write_worksheet(&example[..2], Layout::Columns, Align::Left).unwrap(),
+            "641 963\n 52 842\n  3   8\n*   +\n"
+        );
+        assert_eq!(
+            write_worksheet(&example[..2], Layout::Rows, Align::Left).unwrap(),
+            "123 328\n45  64\n6   98\n*   +\n"
+        );
+    }
+
+    #[test]
+    fn test_layout_errors() {
+        let mut uneven = random_equations(3, 3, 2, &ALL_OPS, 1);
+        uneven[2].nums.pop();
+
+        assert_eq!(
+            write_worksheet(&uneven, Layout::Rows, Align::Left),
+            Err(LayoutError::Ragged {
+                index: 3,
+                expected: 3,
+                found: 2
+            })
+        );
+        assert!(write_worksheet(&uneven, Layout::Columns, Align::Left).is_ok());
+
+        uneven[0].nums.clear();
+        assert_eq!(
+            write_worksheet(&uneven, Layout::Columns, Align::Left),
+            Err(LayoutError::Empty { index: 1 })
+        );
+    }
+}